@@ -0,0 +1,244 @@
+//! Clip paths and filters managed by the [`LyonWriter`].
+use std::rc::Rc;
+
+use lyon_path::Path;
+use usvg::filter::Filter;
+use usvg::tiny_skia_path::PathBuilder;
+use usvg::{ClipPath, Fill, Group, NodeKind, Path as SvgPath, Units};
+
+use crate::shapes::rect_bounds;
+use crate::{
+    check_finite, lyon_path_to_svg_with_attributes, Color, CompositeOperator, FilterBuilder,
+    FilterInput, LyonTranslationError, LyonWriter, MorphologyOperator,
+};
+
+/// Handle to a clip path defined with [`LyonWriter::define_clip`].
+///
+/// Attach it to paths with [`PathProps::clip`](crate::PathProps::clip) and to groups with
+/// [`GroupProps::clip`](crate::GroupProps::clip).
+#[derive(Clone, Debug)]
+pub struct ClipId(pub(crate) Rc<ClipPath>);
+
+impl PartialEq for ClipId {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Handle to a filter defined with [`LyonWriter::define_blur`],
+/// [`LyonWriter::define_glow`] or [`LyonWriter::define_filter`].
+///
+/// Attach it to paths with [`PathProps::filter`](crate::PathProps::filter) and to groups with
+/// [`GroupProps::filter`](crate::GroupProps::filter).
+#[derive(Clone, Debug)]
+pub struct FilterId(pub(crate) Rc<Filter>);
+
+impl PartialEq for FilterId {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T> LyonWriter<T> {
+    /// Define a clip path with the inside of `path`, following the nonzero rule, so that only
+    /// the parts of the nodes that it is attached to that fall inside are drawn.
+    ///
+    /// The path is in the user space of the clipped node, including its own transform. The
+    /// writer assigns an unique id to the clip path, which is written to the `<defs>` of
+    /// the SVG.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, GroupProps, LyonWriter, PathProps, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::math::{point, Box2D};
+    /// use lyon_path::Winding;
+    ///
+    /// let mut frame = Path::builder();
+    /// frame.add_rectangle(&Box2D::new(point(0.0, 0.0), point(2.0, 2.0)), Winding::Positive);
+    /// let mut circle = Path::builder();
+    /// circle.add_circle(point(2.0, 2.0), 2.0, Winding::Positive);
+    /// let circle = circle.build();
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// let clip = writer.define_clip(&frame.build()).expect("Clip should be writable!");
+    /// writer
+    ///     .push_with(&circle, PathProps::new().fill(fill(Color::black(), 1.0)).clip(clip.clone()))
+    ///     .expect("Path should be writable!");
+    /// writer
+    ///     .group_with(GroupProps::new().clip(clip), |g| {
+    ///         g.push(&circle, None, None, None)?;
+    ///         Ok(())
+    ///     })
+    ///     .expect("Group should be writable!");
+    /// let svg = writer.to_svg_string().expect("Paths should be writable!");
+    /// assert!(svg.contains("<clipPath id=\"roarsvg-clip1\""));
+    /// assert_eq!(svg.matches("clip-path=\"url(#roarsvg-clip1)\"").count(), 2);
+    /// ```
+    pub fn define_clip(&mut self, path: &Path) -> Result<ClipId, LyonTranslationError> {
+        check_finite(path.as_slice(), 0)?;
+        let clip_path = lyon_path_to_svg_with_attributes(path, Some(Fill::default()), None, None)
+            .ok_or(LyonTranslationError::SvgFailure)?;
+        Ok(self.clip_from(clip_path))
+    }
+
+    /// Define a clip path with the rectangle `(x, y, width, height)`, which must have some
+    /// area, as [`define_clip`](Self::define_clip) does for paths. Use it to crop nodes to
+    /// the frame of a panel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{GroupProps, LyonWriter};
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// let clip = writer.define_clip_rect((0.0, 0.0, 2.0, 1.0)).expect("Clip should have an area!");
+    /// let props = GroupProps::new().clip(clip);
+    /// ```
+    pub fn define_clip_rect(
+        &mut self,
+        rect: (f32, f32, f32, f32),
+    ) -> Result<ClipId, LyonTranslationError> {
+        let data = PathBuilder::from_rect(rect_bounds(rect)?);
+        let mut clip_path = SvgPath::new(Rc::new(data));
+        clip_path.fill = Some(Fill::default());
+        Ok(self.clip_from(clip_path))
+    }
+
+    fn clip_from(&mut self, clip_path: SvgPath) -> ClipId {
+        let root = usvg::Node::new(NodeKind::Group(Group::default()));
+        root.append(usvg::Node::new(NodeKind::Path(clip_path)));
+        ClipId(Rc::new(ClipPath {
+            id: self.fresh_id("clip"),
+            root,
+            ..ClipPath::default()
+        }))
+    }
+
+    /// Define a gaussian blur with a standard deviation of `std_dev` user units, which must
+    /// be non-negative and finite. The blur is rendered over the bounding box of the
+    /// filtered node grown by half its size on each side, so that the blur is not cut, as
+    /// for a [`FilterBuilder`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, PathProps, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::math::point;
+    /// use lyon_path::Winding;
+    ///
+    /// let mut circle = Path::builder();
+    /// circle.add_circle(point(2.0, 2.0), 2.0, Winding::Positive);
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// let blur = writer.define_blur(0.5).expect("Blur should be valid!");
+    /// writer
+    ///     .push_with(&circle.build(), PathProps::new().fill(fill(Color::black(), 1.0)).filter(blur))
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("<feGaussianBlur"));
+    /// assert!(svg.contains("filter=\"url(#roarsvg-filter1)\""));
+    /// ```
+    pub fn define_blur(&mut self, std_dev: f32) -> Result<FilterId, LyonTranslationError> {
+        let mut filter = FilterBuilder::new();
+        filter.blur(FilterInput::source_graphic(), std_dev);
+        self.define_filter(filter)
+    }
+
+    /// Define a halo of `color` and `opacity` around the filtered node, drawn below it: its
+    /// silhouette grown by `radius` user units and blurred with a standard deviation of
+    /// `std_dev`. Both must be non-negative and finite.
+    ///
+    /// Use it to keep labels legible over busy backgrounds or to highlight paths.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, PathProps, stroke};
+    /// use lyon_path::Path;
+    /// use lyon_path::math::point;
+    ///
+    /// let mut line = Path::builder();
+    /// line.begin(point(0.0, 0.0));
+    /// line.line_to(point(4.0, 2.0));
+    /// line.end(false);
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// let glow = writer
+    ///     .define_glow(Color::new_rgb(255, 255, 0), 0.8, 0.5, 0.25)
+    ///     .expect("Glow should be valid!");
+    /// writer
+    ///     .push_with(&line.build(), PathProps::new().stroke(stroke(Color::black(), 1.0, 0.2)).filter(glow))
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("<feMorphology"));
+    /// ```
+    pub fn define_glow(
+        &mut self,
+        color: Color,
+        opacity: f32,
+        radius: f32,
+        std_dev: f32,
+    ) -> Result<FilterId, LyonTranslationError> {
+        let mut filter = FilterBuilder::new();
+        let silhouette = filter.morphology(
+            FilterInput::source_alpha(),
+            MorphologyOperator::Dilate,
+            radius,
+        );
+        let flood = filter.flood(color, opacity);
+        let halo = filter.composite(flood, silhouette, CompositeOperator::In);
+        let halo = filter.blur(halo, std_dev);
+        filter.merge([halo, FilterInput::source_graphic()]);
+        self.define_filter(filter)
+    }
+
+    /// Define a custom filter with the primitives of `filter`, which must have at least one
+    /// and use finite parameters, non-negative for sizes, in a region with some area.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, FilterBuilder, FilterInput, LyonWriter, PathProps, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::math::point;
+    /// use lyon_path::Winding;
+    ///
+    /// let mut circle = Path::builder();
+    /// circle.add_circle(point(2.0, 2.0), 2.0, Winding::Positive);
+    ///
+    /// let mut shadow = FilterBuilder::new();
+    /// let offset = shadow.offset(FilterInput::source_alpha(), 0.5, 0.5);
+    /// let blurred = shadow.blur(offset, 0.3);
+    /// shadow.merge([blurred, FilterInput::source_graphic()]);
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// let shadow = writer.define_filter(shadow).expect("Filter should be valid!");
+    /// writer
+    ///     .push_with(&circle.build(), PathProps::new().fill(fill(Color::white(), 1.0)).filter(shadow))
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("<feOffset"));
+    /// assert!(svg.contains("<feMergeNode in=\"result2\"/>"));
+    /// ```
+    pub fn define_filter(
+        &mut self,
+        filter: FilterBuilder,
+    ) -> Result<FilterId, LyonTranslationError> {
+        if filter.invalid || filter.primitives.is_empty() || !filter.has_valid_inputs() {
+            return Err(LyonTranslationError::SvgFailure);
+        }
+        let rect = filter
+            .region_rect()
+            .ok_or(LyonTranslationError::SvgFailure)?;
+        Ok(FilterId(Rc::new(Filter {
+            id: self.fresh_id("filter"),
+            units: Units::ObjectBoundingBox,
+            primitive_units: Units::UserSpaceOnUse,
+            rect,
+            primitives: filter.primitives,
+        })))
+    }
+}
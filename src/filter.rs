@@ -0,0 +1,292 @@
+//! Builder of filters from the primitives of SVG, for effects not covered by the writer.
+use usvg::filter::{
+    Blend, ColorInterpolation, ColorMatrix, ColorMatrixKind, Composite, CompositeOperator, Flood,
+    GaussianBlur, Input, Kind, Merge, Morphology, MorphologyOperator, Offset, Primitive,
+};
+use usvg::{BlendMode, Color, NonZeroRect, Opacity, PositiveF32};
+
+/// Input of a filter primitive: the graphic that the filter is applied to, its alpha channel
+/// or the output of a previous primitive of the same [`FilterBuilder`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterInput(pub(crate) Input);
+
+impl FilterInput {
+    /// The filtered node, as it would be rendered without the filter.
+    pub fn source_graphic() -> Self {
+        Self(Input::SourceGraphic)
+    }
+
+    /// The alpha channel of the filtered node, as a black silhouette.
+    pub fn source_alpha() -> Self {
+        Self(Input::SourceAlpha)
+    }
+}
+
+/// Sequence of filter primitives, defined in the writer with
+/// [`LyonWriter::define_filter`](crate::LyonWriter::define_filter).
+///
+/// Each primitive returns a [`FilterInput`] with its output, to be used by the following
+/// ones. The output of the last primitive is what gets rendered.
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{FilterBuilder, FilterInput};
+///
+/// // a drop shadow
+/// let mut shadow = FilterBuilder::new();
+/// let offset = shadow.offset(FilterInput::source_alpha(), 0.5, 0.5);
+/// let blurred = shadow.blur(offset, 0.3);
+/// shadow.merge([blurred, FilterInput::source_graphic()]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FilterBuilder {
+    pub(crate) primitives: Vec<Primitive>,
+    region: (f32, f32, f32, f32),
+    color_interpolation: ColorInterpolation,
+    pub(crate) invalid: bool,
+}
+
+impl Default for FilterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FilterBuilder {
+    /// Empty filter rendered over the bounding box of the filtered node grown by half its
+    /// size on each side, with colors interpolated in sRGB.
+    ///
+    /// Since the region is relative to the bounding box, nodes whose box has no area (e.g.,
+    /// horizontal lines) are not rendered with the filter.
+    pub fn new() -> Self {
+        Self {
+            primitives: Vec::new(),
+            region: (-0.5, -0.5, 2.0, 2.0),
+            color_interpolation: ColorInterpolation::SRGB,
+            invalid: false,
+        }
+    }
+
+    /// Set the region where the filter is rendered as `(x, y, width, height)` in fractions of
+    /// the bounding box of the filtered node. Outside of it, the result is transparent.
+    pub fn region(&mut self, x: f32, y: f32, width: f32, height: f32) -> &mut Self {
+        self.region = (x, y, width, height);
+        self
+    }
+
+    /// Interpolate the colors of the following primitives in linearRGB, the default of SVG,
+    /// instead of sRGB.
+    pub fn linear_rgb(&mut self) -> &mut Self {
+        self.color_interpolation = ColorInterpolation::LinearRGB;
+        self
+    }
+
+    fn push(&mut self, kind: Kind) -> FilterInput {
+        let result = format!("result{}", self.primitives.len() + 1);
+        self.primitives.push(Primitive {
+            x: None,
+            y: None,
+            width: None,
+            height: None,
+            color_interpolation: self.color_interpolation,
+            result: result.clone(),
+            kind,
+        });
+        FilterInput(Input::Reference(result))
+    }
+
+    /// Check that `value` is non-negative and finite, as the sizes of primitives must be.
+    fn positive(&mut self, value: f32) -> PositiveF32 {
+        PositiveF32::new(value).unwrap_or_else(|| {
+            self.invalid = true;
+            PositiveF32::ZERO
+        })
+    }
+
+    /// Check that `value` is finite, as the offsets and factors of primitives must be.
+    fn finite(&mut self, value: f32) -> f32 {
+        self.invalid |= !value.is_finite();
+        value
+    }
+
+    /// Fill the filter region with `color` and `opacity` (clamped to `[0, 1]`), as `feFlood`.
+    pub fn flood(&mut self, color: Color, opacity: f32) -> FilterInput {
+        self.push(Kind::Flood(Flood {
+            color,
+            opacity: Opacity::new_clamped(opacity),
+        }))
+    }
+
+    /// Translate `input` by `(dx, dy)` user units, as `feOffset`.
+    pub fn offset(&mut self, input: FilterInput, dx: f32, dy: f32) -> FilterInput {
+        let (dx, dy) = (self.finite(dx), self.finite(dy));
+        self.push(Kind::Offset(Offset {
+            input: input.0,
+            dx,
+            dy,
+        }))
+    }
+
+    /// Blur `input` with a gaussian of standard deviation `std_dev` user units, as
+    /// `feGaussianBlur`.
+    pub fn blur(&mut self, input: FilterInput, std_dev: f32) -> FilterInput {
+        let std_dev = self.positive(std_dev);
+        self.push(Kind::GaussianBlur(GaussianBlur {
+            input: input.0,
+            std_dev_x: std_dev,
+            std_dev_y: std_dev,
+        }))
+    }
+
+    /// Thin or fatten `input` by `radius` user units, as `feMorphology`.
+    pub fn morphology(
+        &mut self,
+        input: FilterInput,
+        operator: MorphologyOperator,
+        radius: f32,
+    ) -> FilterInput {
+        let radius = self.positive(radius);
+        self.push(Kind::Morphology(Morphology {
+            input: input.0,
+            operator,
+            radius_x: radius,
+            radius_y: radius,
+        }))
+    }
+
+    /// Transform the colors of `input` with a row-major 4x5 `matrix` that maps
+    /// `[r, g, b, a, 1]` to `[r, g, b, a]`, as `feColorMatrix`.
+    pub fn color_matrix(&mut self, input: FilterInput, matrix: [f32; 20]) -> FilterInput {
+        let matrix = matrix.map(|value| self.finite(value)).to_vec();
+        self.push(Kind::ColorMatrix(ColorMatrix {
+            input: input.0,
+            kind: ColorMatrixKind::Matrix(matrix),
+        }))
+    }
+
+    /// Scale the saturation of `input` by `factor`, where 0 is grayscale, as `feColorMatrix`.
+    pub fn saturate(&mut self, input: FilterInput, factor: f32) -> FilterInput {
+        let factor = self.positive(factor);
+        self.push(Kind::ColorMatrix(ColorMatrix {
+            input: input.0,
+            kind: ColorMatrixKind::Saturate(factor),
+        }))
+    }
+
+    /// Rotate the hue of `input` by `angle` degrees, as `feColorMatrix`.
+    pub fn hue_rotate(&mut self, input: FilterInput, angle: f32) -> FilterInput {
+        let angle = self.finite(angle);
+        self.push(Kind::ColorMatrix(ColorMatrix {
+            input: input.0,
+            kind: ColorMatrixKind::HueRotate(angle),
+        }))
+    }
+
+    /// Composite `input` with `below` using the Porter-Duff `operator`, as `feComposite`.
+    pub fn composite(
+        &mut self,
+        input: FilterInput,
+        below: FilterInput,
+        operator: CompositeOperator,
+    ) -> FilterInput {
+        if let CompositeOperator::Arithmetic { k1, k2, k3, k4 } = operator {
+            self.invalid |= ![k1, k2, k3, k4].iter().all(|k| k.is_finite());
+        }
+        self.push(Kind::Composite(Composite {
+            input1: input.0,
+            input2: below.0,
+            operator,
+        }))
+    }
+
+    /// Blend `input` over `below` with `mode`, as `feBlend`.
+    pub fn blend(
+        &mut self,
+        input: FilterInput,
+        below: FilterInput,
+        mode: BlendMode,
+    ) -> FilterInput {
+        self.push(Kind::Blend(Blend {
+            input1: input.0,
+            input2: below.0,
+            mode,
+        }))
+    }
+
+    /// Paint `inputs` over each other, the first one at the bottom, as `feMerge`.
+    pub fn merge(&mut self, inputs: impl IntoIterator<Item = FilterInput>) -> FilterInput {
+        self.push(Kind::Merge(Merge {
+            inputs: inputs.into_iter().map(|input| input.0).collect(),
+        }))
+    }
+
+    /// Region of the filter, if it has an area.
+    pub(crate) fn region_rect(&self) -> Option<NonZeroRect> {
+        let (x, y, width, height) = self.region;
+        NonZeroRect::from_xywh(x, y, width, height)
+    }
+
+    /// Whether every primitive only references the outputs of the primitives before it.
+    pub(crate) fn has_valid_inputs(&self) -> bool {
+        self.primitives.iter().enumerate().all(|(i, primitive)| {
+            let inputs: Vec<&Input> = match &primitive.kind {
+                Kind::Blend(fe) => vec![&fe.input1, &fe.input2],
+                Kind::ColorMatrix(fe) => vec![&fe.input],
+                Kind::Composite(fe) => vec![&fe.input1, &fe.input2],
+                Kind::GaussianBlur(fe) => vec![&fe.input],
+                Kind::Merge(fe) => fe.inputs.iter().collect(),
+                Kind::Morphology(fe) => vec![&fe.input],
+                Kind::Offset(fe) => vec![&fe.input],
+                _ => Vec::new(),
+            };
+            inputs.into_iter().all(|input| match input {
+                Input::Reference(name) => self.primitives[..i]
+                    .iter()
+                    .any(|previous| &previous.result == name),
+                _ => true,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inputs_must_come_before_their_use() {
+        let mut filter = FilterBuilder::new();
+        let blurred = filter.blur(FilterInput::source_alpha(), 1.0);
+        filter.merge([blurred.clone(), FilterInput::source_graphic()]);
+        assert!(filter.has_valid_inputs());
+        // an output of another builder, which does not exist in this one
+        let mut other = FilterBuilder::new();
+        other.offset(FilterInput::source_graphic(), 1.0, 1.0);
+        let foreign = other.blur(blurred, 1.0);
+        let mut filter = FilterBuilder::new();
+        filter.offset(foreign, 1.0, 1.0);
+        assert!(!filter.has_valid_inputs());
+        assert!(!filter.invalid);
+        filter.blur(FilterInput::source_graphic(), -1.0);
+        assert!(filter.invalid);
+    }
+
+    #[test]
+    fn settings_apply_to_the_following_primitives() {
+        let mut filter = FilterBuilder::new();
+        let blurred = filter.blur(FilterInput::source_alpha(), 1.0);
+        filter.linear_rgb().region(0.0, 0.0, 1.0, 1.0);
+        filter.merge([blurred, FilterInput::source_graphic()]);
+        let interpolations: Vec<_> = filter
+            .primitives
+            .iter()
+            .map(|primitive| primitive.color_interpolation)
+            .collect();
+        assert_eq!(
+            interpolations,
+            [ColorInterpolation::SRGB, ColorInterpolation::LinearRGB]
+        );
+        assert!(filter.region_rect().is_some());
+    }
+}
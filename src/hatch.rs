@@ -0,0 +1,178 @@
+//! Hatching of closed paths with parallel lines, which stay legible in grayscale prints.
+use lyon_path::iterator::PathIterator;
+use lyon_path::math::{point, Point};
+use lyon_path::{Event, Path};
+
+use crate::FillRule;
+
+/// Parallel lines that fill the inside of a path, see
+/// [`LyonWriter::push_hatched`](crate::LyonWriter::push_hatched).
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{FillRule, Hatch};
+///
+/// // diagonal lines every 2 units, starting 1 unit away from the origin
+/// let hatch = Hatch::new(45.0, 2.0).offset(1.0).rule(FillRule::EvenOdd);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hatch {
+    angle: f32,
+    spacing: f32,
+    offset: f32,
+    rule: FillRule,
+}
+
+impl Hatch {
+    /// Lines rotated `angle` degrees clockwise from the x axis, as in
+    /// [`SvgTransform::from_rotate`](crate::SvgTransform::from_rotate), `spacing` units apart.
+    pub fn new(angle: f32, spacing: f32) -> Self {
+        Self {
+            angle,
+            spacing,
+            offset: 0.0,
+            rule: FillRule::NonZero,
+        }
+    }
+
+    /// Shift the lines by `offset` units across their direction. By default, a line passes
+    /// through the origin, so that the hatches of adjacent shapes line up.
+    pub fn offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Set the rule that decides which parts of the path are inside, as for fills.
+    pub fn rule(mut self, rule: FillRule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Lines of the hatch clipped to the inside of `path`, each one as an open subpath.
+    ///
+    /// Curves are flattened to a tenth of the spacing and open subpaths are closed, as for
+    /// fills. A non-positive or non-finite spacing produces no lines.
+    pub fn lines(&self, path: &Path) -> Path {
+        let mut builder = Path::builder();
+        if !(self.spacing > 0.0 && self.spacing.is_finite()) {
+            return builder.build();
+        }
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        // coordinates along the lines (u) and across them (v)
+        let to_uv = |p: Point| (p.x * cos + p.y * sin, p.y * cos - p.x * sin);
+        let from_uv = |u: f32, v: f32| point(u * cos - v * sin, u * sin + v * cos);
+        let mut edges = Vec::new();
+        for event in path.iter().flattened(self.spacing / 10.0) {
+            match event {
+                Event::Line { from, to } => edges.push((to_uv(from), to_uv(to))),
+                Event::End { last, first, .. } if last != first => {
+                    edges.push((to_uv(last), to_uv(first)))
+                }
+                _ => (),
+            }
+        }
+        let (v_min, v_max) = edges
+            .iter()
+            .flat_map(|&(a, b)| [a.1, b.1])
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+        if !(v_min.is_finite() && v_max.is_finite()) {
+            return builder.build();
+        }
+        let first = ((v_min - self.offset) / self.spacing).ceil() as i64;
+        let last = ((v_max - self.offset) / self.spacing).floor() as i64;
+        let mut crossings = Vec::new();
+        for k in first..=last {
+            let v = self.offset + k as f32 * self.spacing;
+            crossings.clear();
+            // edges are half-open, so that lines through vertices cross them once
+            for &((u1, v1), (u2, v2)) in &edges {
+                if (v1 <= v) != (v2 <= v) {
+                    let u = u1 + (v - v1) / (v2 - v1) * (u2 - u1);
+                    crossings.push((u, if v2 > v1 { 1 } else { -1 }));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut winding = 0;
+            let mut start = None;
+            for &(u, direction) in &crossings {
+                winding += direction;
+                let inside = match self.rule {
+                    FillRule::NonZero => winding != 0,
+                    FillRule::EvenOdd => winding % 2 != 0,
+                };
+                match (start, inside) {
+                    (None, true) => start = Some(u),
+                    (Some(u_start), false) => {
+                        start = None;
+                        if u > u_start {
+                            builder.begin(from_uv(u_start, v));
+                            builder.line_to(from_uv(u, v));
+                            builder.end(false);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lyon_path::math::Box2D;
+    use lyon_path::Winding;
+
+    fn segments(path: &Path) -> Vec<[(f32, f32); 2]> {
+        path.iter()
+            .filter_map(|event| match event {
+                Event::Line { from, to } => Some([(from.x, from.y), (to.x, to.y)]),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hatches_are_clipped_to_the_inside() {
+        let mut builder = Path::builder();
+        builder.add_rectangle(
+            &Box2D::new(point(0.0, 0.0), point(4.0, 3.0)),
+            Winding::Positive,
+        );
+        builder.add_rectangle(
+            &Box2D::new(point(1.0, 1.0), point(3.0, 2.0)),
+            Winding::Positive,
+        );
+        let path = builder.build();
+        // the hole is only a hole with the evenodd rule
+        let hatch = Hatch::new(0.0, 1.0).offset(0.5);
+        assert_eq!(
+            segments(&hatch.lines(&path)),
+            [
+                [(0.0, 0.5), (4.0, 0.5)],
+                [(0.0, 1.5), (4.0, 1.5)],
+                [(0.0, 2.5), (4.0, 2.5)]
+            ]
+        );
+        let hatch = hatch.rule(FillRule::EvenOdd);
+        assert_eq!(
+            segments(&hatch.lines(&path)),
+            [
+                [(0.0, 0.5), (4.0, 0.5)],
+                [(0.0, 1.5), (1.0, 1.5)],
+                [(3.0, 1.5), (4.0, 1.5)],
+                [(0.0, 2.5), (4.0, 2.5)]
+            ]
+        );
+        // vertical lines, which cross the hole with the nonzero rule
+        let vertical = segments(&Hatch::new(90.0, 2.0).offset(1.0).lines(&path));
+        assert_eq!(vertical.len(), 2);
+        for [(x1, y1), (x2, y2)] in vertical {
+            assert!((x1 - x2).abs() < 1e-5 && (y1 - y2).abs() > 2.99);
+        }
+    }
+}
@@ -0,0 +1,434 @@
+//! Conversion of the geometry of other 2D crates to the path data of the SVG.
+use crate::{non_finite_point, EventSink, LyonTranslationError, LyonWriter};
+use lyon_path::math::Point;
+use lyon_path::traits::{Build, PathBuilder as LyonBuilder};
+use lyon_path::{Attributes, EndpointId, PathEvent};
+use usvg::tiny_skia_path::{Path as PathData, PathBuilder};
+use usvg::{Fill, Stroke, Transform};
+
+/// Convert `(x, y)` to the precision of the SVG, failing if it is not finite as an `f32`.
+fn to_point(x: f64, y: f64, node: usize) -> Result<(f32, f32), LyonTranslationError> {
+    let (x, y) = (x as f32, y as f32);
+    if x.is_finite() && y.is_finite() {
+        Ok((x, y))
+    } else {
+        Err(LyonTranslationError::NonFiniteCoordinate { node, x, y })
+    }
+}
+
+/// Parse the SVG path data `d`, pushed as the `node`-th node. Relative commands, shorthands
+/// and arcs are converted to absolute lines and curves.
+pub(crate) fn svg_d_to_usvg(d: &str, node: usize) -> Result<PathData, LyonTranslationError> {
+    use svgtypes::SimplePathSegment as Segment;
+    let mut builder = PathBuilder::new();
+    for segment in svgtypes::SimplifyingPathParser::from(d) {
+        match segment.map_err(|e| LyonTranslationError::InvalidPathData(e.to_string()))? {
+            Segment::MoveTo { x, y } => {
+                let (x, y) = to_point(x, y, node)?;
+                builder.move_to(x, y)
+            }
+            Segment::LineTo { x, y } => {
+                let (x, y) = to_point(x, y, node)?;
+                builder.line_to(x, y)
+            }
+            Segment::Quadratic { x1, y1, x, y } => {
+                let (x1, y1) = to_point(x1, y1, node)?;
+                let (x, y) = to_point(x, y, node)?;
+                builder.quad_to(x1, y1, x, y)
+            }
+            Segment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                let (x1, y1) = to_point(x1, y1, node)?;
+                let (x2, y2) = to_point(x2, y2, node)?;
+                let (x, y) = to_point(x, y, node)?;
+                builder.cubic_to(x1, y1, x2, y2, x, y)
+            }
+            Segment::ClosePath => builder.close(),
+        }
+    }
+    builder.finish().ok_or(LyonTranslationError::SvgFailure)
+}
+
+/// Convert the outline of a [`kurbo::Shape`], pushed as the `node`-th node, flattening its
+/// arcs (e.g., of circles) within `1e-3` units.
+#[cfg(feature = "kurbo")]
+pub(crate) fn kurbo_to_usvg(
+    shape: &impl kurbo::Shape,
+    node: usize,
+) -> Result<PathData, LyonTranslationError> {
+    use kurbo::PathEl;
+    let mut builder = PathBuilder::new();
+    for element in shape.path_elements(1e-3) {
+        match element {
+            PathEl::MoveTo(p) => {
+                let (x, y) = to_point(p.x, p.y, node)?;
+                builder.move_to(x, y)
+            }
+            PathEl::LineTo(p) => {
+                let (x, y) = to_point(p.x, p.y, node)?;
+                builder.line_to(x, y)
+            }
+            PathEl::QuadTo(p1, p) => {
+                let (x1, y1) = to_point(p1.x, p1.y, node)?;
+                let (x, y) = to_point(p.x, p.y, node)?;
+                builder.quad_to(x1, y1, x, y)
+            }
+            PathEl::CurveTo(p1, p2, p) => {
+                let (x1, y1) = to_point(p1.x, p1.y, node)?;
+                let (x2, y2) = to_point(p2.x, p2.y, node)?;
+                let (x, y) = to_point(p.x, p.y, node)?;
+                builder.cubic_to(x1, y1, x2, y2, x, y)
+            }
+            PathEl::ClosePath => builder.close(),
+        }
+    }
+    builder.finish().ok_or(LyonTranslationError::SvgFailure)
+}
+
+/// Convert a geometry of the geo ecosystem, pushed as the `node`-th node. Lines are left open
+/// and polygon rings are closed, while points have no outline and are skipped.
+#[cfg(feature = "geo")]
+pub(crate) fn geo_to_usvg<T: geo_types::CoordNum + Into<f64>>(
+    geometry: &geo_types::Geometry<T>,
+    node: usize,
+) -> Result<PathData, LyonTranslationError> {
+    let mut builder = PathBuilder::new();
+    append_geo(&mut builder, geometry, node)?;
+    builder.finish().ok_or(LyonTranslationError::SvgFailure)
+}
+
+#[cfg(feature = "geo")]
+fn append_geo<T: geo_types::CoordNum + Into<f64>>(
+    builder: &mut PathBuilder,
+    geometry: &geo_types::Geometry<T>,
+    node: usize,
+) -> Result<(), LyonTranslationError> {
+    use geo_types::Geometry;
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => Ok(()),
+        Geometry::Line(line) => append_ring(builder, &[line.start, line.end], false, node),
+        Geometry::LineString(line) => append_ring(builder, &line.0, false, node),
+        Geometry::MultiLineString(lines) => lines
+            .iter()
+            .try_for_each(|line| append_ring(builder, &line.0, false, node)),
+        Geometry::Polygon(polygon) => append_polygon(builder, polygon, node),
+        Geometry::MultiPolygon(polygons) => polygons
+            .iter()
+            .try_for_each(|polygon| append_polygon(builder, polygon, node)),
+        Geometry::Rect(rect) => append_polygon(builder, &rect.to_polygon(), node),
+        Geometry::Triangle(triangle) => append_polygon(builder, &triangle.to_polygon(), node),
+        Geometry::GeometryCollection(geometries) => geometries
+            .iter()
+            .try_for_each(|geometry| append_geo(builder, geometry, node)),
+    }
+}
+
+#[cfg(feature = "geo")]
+fn append_polygon<T: geo_types::CoordNum + Into<f64>>(
+    builder: &mut PathBuilder,
+    polygon: &geo_types::Polygon<T>,
+    node: usize,
+) -> Result<(), LyonTranslationError> {
+    std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .try_for_each(|ring| append_ring(builder, &ring.0, true, node))
+}
+
+#[cfg(feature = "geo")]
+fn append_ring<T: geo_types::CoordNum + Into<f64>>(
+    builder: &mut PathBuilder,
+    coords: &[geo_types::Coord<T>],
+    close: bool,
+    node: usize,
+) -> Result<(), LyonTranslationError> {
+    // rings repeat their first coordinate, which is drawn by the close command
+    let coords = match coords {
+        [first, .., last] if close && first == last => &coords[..coords.len() - 1],
+        _ => coords,
+    };
+    if coords.len() < 2 {
+        return Ok(());
+    }
+    for (i, coord) in coords.iter().enumerate() {
+        let (x, y) = to_point(coord.x.into(), coord.y.into(), node)?;
+        if i == 0 {
+            builder.move_to(x, y);
+        } else {
+            builder.line_to(x, y);
+        }
+    }
+    if close {
+        builder.close();
+    }
+    Ok(())
+}
+
+/// Reconstruct the outline of the triangles of a tessellated mesh, pushed as the `node`-th
+/// node, as rings of its boundary edges.
+///
+/// The triangles are oriented alike and the edges they share in opposite directions cancel
+/// out, which keeps the winding number of every point. Thus, filling the rings with the
+/// nonzero rule covers the same area as the triangles, even if they overlap.
+#[cfg(feature = "tessellation")]
+pub(crate) fn mesh_to_usvg<V: lyon_path::Position, I: Copy + Into<u32>>(
+    buffers: &lyon_tessellation::VertexBuffers<V, I>,
+    node: usize,
+) -> Result<PathData, LyonTranslationError> {
+    use std::collections::{BTreeMap, HashMap};
+    // tessellators may repeat vertices at the same position
+    let mut ids = HashMap::new();
+    let mut points = Vec::new();
+    let vertices = buffers
+        .vertices
+        .iter()
+        .map(|vertex| {
+            let p = vertex.position();
+            let (x, y) = to_point(p.x as f64, p.y as f64, node)?;
+            Ok(*ids.entry((x.to_bits(), y.to_bits())).or_insert_with(|| {
+                points.push((x, y));
+                points.len() - 1
+            }))
+        })
+        .collect::<Result<Vec<usize>, LyonTranslationError>>()?;
+    let mut triangles = Vec::new();
+    for triangle in buffers.indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| {
+            vertices
+                .get(i.into() as usize)
+                .copied()
+                .ok_or(LyonTranslationError::SvgFailure)
+        });
+        let [a, b, c] = [a?, b?, c?];
+        let [pa, pb, pc] = [a, b, c].map(|i| points[i]);
+        let area = (pb.0 - pa.0) * (pc.1 - pa.1) - (pb.1 - pa.1) * (pc.0 - pa.0);
+        if area > 0.0 {
+            triangles.push([a, b, c]);
+        } else if area < 0.0 {
+            triangles.push([a, c, b]);
+        }
+    }
+    let mut edges: HashMap<(usize, usize), i32> = HashMap::new();
+    for [a, b, c] in triangles {
+        for edge in [(a, b), (b, c), (c, a)] {
+            *edges.entry(edge).or_default() += 1;
+        }
+    }
+    let mut next: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (&(a, b), &count) in edges.iter() {
+        let net = count - edges.get(&(b, a)).copied().unwrap_or_default();
+        for _ in 0..net {
+            next.entry(a).or_default().push(b);
+        }
+    }
+    for successors in next.values_mut() {
+        successors.sort_unstable();
+    }
+    let mut builder = PathBuilder::new();
+    while let Some(start) = next
+        .iter()
+        .find_map(|(&from, successors)| (!successors.is_empty()).then_some(from))
+    {
+        builder.move_to(points[start].0, points[start].1);
+        let mut current = start;
+        // every vertex is left as many times as it is reached, so the ring gets back to start
+        while let Some(to) = next
+            .get_mut(&current)
+            .and_then(|successors| successors.pop())
+        {
+            if to == start {
+                break;
+            }
+            builder.line_to(points[to].0, points[to].1);
+            current = to;
+        }
+        builder.close();
+    }
+    builder.finish().ok_or(LyonTranslationError::SvgFailure)
+}
+
+/// Adapter of lyon's builder traits that translates the commands of a path of a
+/// [`LyonWriter`] as they come, created with [`LyonWriter::path_sink`].
+///
+/// Custom attributes are ignored.
+pub struct PathSink<'w, T> {
+    writer: &'w mut LyonWriter<T>,
+    sink: EventSink,
+    fill: Option<Fill>,
+    stroke: Option<Stroke>,
+    transform: Option<Transform>,
+    first: Point,
+    last: Point,
+    endpoints: u32,
+    non_finite: Option<Point>,
+}
+
+impl<'w, T> PathSink<'w, T> {
+    pub(crate) fn new(
+        writer: &'w mut LyonWriter<T>,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<Transform>,
+    ) -> Self {
+        Self {
+            writer,
+            sink: EventSink::new(PathBuilder::new()),
+            fill,
+            stroke,
+            transform,
+            first: Point::zero(),
+            last: Point::zero(),
+            endpoints: 0,
+            non_finite: None,
+        }
+    }
+
+    fn event(&mut self, event: PathEvent) {
+        if self.non_finite.is_none() {
+            self.non_finite = non_finite_point(&event);
+        }
+        self.sink.event(event);
+    }
+
+    fn endpoint(&mut self, to: Point) -> EndpointId {
+        self.last = to;
+        self.endpoints += 1;
+        EndpointId(self.endpoints - 1)
+    }
+}
+
+impl<T> LyonBuilder for PathSink<'_, T> {
+    fn num_attributes(&self) -> usize {
+        0
+    }
+
+    fn begin(&mut self, at: Point, _custom_attributes: Attributes) -> EndpointId {
+        self.first = at;
+        self.event(PathEvent::Begin { at });
+        self.endpoint(at)
+    }
+
+    fn end(&mut self, close: bool) {
+        self.event(PathEvent::End {
+            last: self.last,
+            first: self.first,
+            close,
+        });
+    }
+
+    fn line_to(&mut self, to: Point, _custom_attributes: Attributes) -> EndpointId {
+        self.event(PathEvent::Line {
+            from: self.last,
+            to,
+        });
+        self.endpoint(to)
+    }
+
+    fn quadratic_bezier_to(
+        &mut self,
+        ctrl: Point,
+        to: Point,
+        _custom_attributes: Attributes,
+    ) -> EndpointId {
+        self.event(PathEvent::Quadratic {
+            from: self.last,
+            ctrl,
+            to,
+        });
+        self.endpoint(to)
+    }
+
+    fn cubic_bezier_to(
+        &mut self,
+        ctrl1: Point,
+        ctrl2: Point,
+        to: Point,
+        _custom_attributes: Attributes,
+    ) -> EndpointId {
+        self.event(PathEvent::Cubic {
+            from: self.last,
+            ctrl1,
+            ctrl2,
+            to,
+        });
+        self.endpoint(to)
+    }
+}
+
+impl<T> Build for PathSink<'_, T> {
+    type PathType = Result<(), LyonTranslationError>;
+
+    /// Add the path to the writer.
+    fn build(self) -> Self::PathType {
+        if let Some(point) = self.non_finite {
+            return Err(LyonTranslationError::NonFiniteCoordinate {
+                node: self.writer.nodes.len(),
+                x: point.x,
+                y: point.y,
+            });
+        }
+        let data = self.sink.finish().ok_or(LyonTranslationError::SvgFailure)?;
+        self.writer
+            .push_skia_path(data, self.fill, self.stroke, self.transform)?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "tessellation"))]
+mod tests {
+    use super::*;
+    use lyon_tessellation::VertexBuffers;
+    use usvg::tiny_skia_path::PathVerb;
+
+    #[test]
+    fn meshes_are_traced_along_their_boundary() {
+        let square = VertexBuffers {
+            vertices: vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0], [1.0, 1.0]],
+            indices: vec![0u16, 1, 2, 0, 4, 3],
+        };
+        let data = mesh_to_usvg(&square, 0).unwrap();
+        assert_eq!(
+            data.verbs(),
+            [
+                PathVerb::Move,
+                PathVerb::Line,
+                PathVerb::Line,
+                PathVerb::Line,
+                PathVerb::Close
+            ]
+        );
+        let disjoint = VertexBuffers {
+            vertices: vec![
+                [0.0, 0.0],
+                [1.0, 0.0],
+                [0.0, 1.0],
+                [5.0, 5.0],
+                [6.0, 5.0],
+                [5.0, 6.0],
+            ],
+            indices: vec![0u32, 1, 2, 3, 5, 4],
+        };
+        let data = mesh_to_usvg(&disjoint, 0).unwrap();
+        // the second triangle is reoriented like the first one
+        assert_eq!(
+            data.points()[3..]
+                .iter()
+                .map(|p| (p.x, p.y))
+                .collect::<Vec<_>>(),
+            [(5.0, 5.0), (6.0, 5.0), (5.0, 6.0)]
+        );
+        let dangling = VertexBuffers {
+            vertices: vec![[0.0, 0.0], [1.0, 0.0]],
+            indices: vec![0u32, 1, 2],
+        };
+        assert!(matches!(
+            mesh_to_usvg(&dangling, 0),
+            Err(LyonTranslationError::SvgFailure)
+        ));
+    }
+}
@@ -1,61 +1,227 @@
 use crate::LyonTranslationError;
 use std::path::Path;
-use usvg::{TreeWriting, XmlOptions};
+
+/// Write to any [`Write`] sink.
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub fn to_writer<W: std::io::Write>(
+    data: &[u8],
+    mut writer: W,
+) -> Result<(), LyonTranslationError> {
+    writer
+        .write_all(data)
+        .and_then(|_| writer.flush())
+        .map_err(|e| LyonTranslationError::IoWrite(Box::new(e)))
+}
+
+/// Write gzip-compressed to file (`.svgz`).
+#[cfg(all(feature = "flate2", not(target_arch = "wasm32")))]
+pub fn to_svgz_file<P: AsRef<Path>>(svg: &str, file_path: P) -> Result<(), LyonTranslationError> {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    let output =
+        std::fs::File::create(file_path).map_err(|e| LyonTranslationError::IoWrite(Box::new(e)))?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    encoder
+        .write_all(svg.as_bytes())
+        .and_then(|_| encoder.finish())
+        .map(|_| ())
+        .map_err(|e| LyonTranslationError::IoWrite(Box::new(e)))
+}
+
+/// Wrap `data` in a [`web_sys::Blob`] of `mime_type`, to be handed over to JS.
+///
+/// It does not access the DOM, so it can be called from web workers.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn to_blob(data: &[u8], mime_type: &str) -> Result<web_sys::Blob, LyonTranslationError> {
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    web_sys::Blob::new_with_u8_array_sequence_and_options(
+        &js_sys::Array::of1(&js_sys::Uint8Array::from(data)),
+        &options,
+    )
+    .map_err(|e| LyonTranslationError::IoWrite(format!("error writing blob: {:?}", e).into()))
+}
+
+/// Set `svg` as the content of the DOM element with id `element_id`.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn to_element(svg: &str, element_id: &str) -> Result<(), LyonTranslationError> {
+    let element = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(element_id))
+        .ok_or_else(|| {
+            LyonTranslationError::IoWrite(format!("no element with id {element_id}").into())
+        })?;
+    element.set_inner_html(svg);
+    Ok(())
+}
+
+/// Write to a file chosen by the user in the save dialog of the browser, which suggests
+/// `file_name`, with the [File System Access API](https://developer.mozilla.org/en-US/docs/Web/API/Window/showSaveFilePicker).
+///
+/// Falls back to [`to_file`] in browsers without the API.
+#[cfg(all(feature = "save-picker", target_arch = "wasm32"))]
+pub async fn to_picked_file(data: &[u8], file_name: &str) -> Result<(), LyonTranslationError> {
+    use js_sys::{Array, Function, Object, Promise, Reflect};
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+
+    let js_error = |e: JsValue| LyonTranslationError::IoWrite(format!("{:?}", e).into());
+    let window = web_sys::window()
+        .ok_or("no window")
+        .map_err(|e| LyonTranslationError::IoWrite(e.into()))?;
+    // the API is not in the stable web-sys bindings, so it is looked up dynamically
+    let Ok(picker) = Reflect::get(&window, &"showSaveFilePicker".into())
+        .map_err(js_error)?
+        .dyn_into::<Function>()
+    else {
+        return to_file(data, file_name);
+    };
+    let mime = mime_type(file_name);
+    let accept = Object::new();
+    let extensions = Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| Array::of1(&format!(".{ext}").into()))
+        .unwrap_or_default();
+    Reflect::set(&accept, &mime.into(), &extensions).map_err(js_error)?;
+    let file_type = Object::new();
+    Reflect::set(&file_type, &"accept".into(), &accept).map_err(js_error)?;
+    let options = Object::new();
+    Reflect::set(
+        &options,
+        &"suggestedName".into(),
+        &download_name(file_name).into(),
+    )
+    .map_err(js_error)?;
+    Reflect::set(&options, &"types".into(), &Array::of1(&file_type)).map_err(js_error)?;
+
+    let handle: web_sys::FileSystemFileHandle = JsFuture::from(
+        picker
+            .call1(&window, &options)
+            .map_err(js_error)?
+            .unchecked_into::<Promise>(),
+    )
+    .await
+    .map_err(js_error)?
+    .unchecked_into();
+    let writable: web_sys::FileSystemWritableFileStream = JsFuture::from(handle.create_writable())
+        .await
+        .map_err(js_error)?
+        .unchecked_into();
+    JsFuture::from(writable.write_with_u8_array(data).map_err(js_error)?)
+        .await
+        .map_err(js_error)?;
+    JsFuture::from(writable.close()).await.map_err(js_error)?;
+    Ok(())
+}
+
+/// MIME type of the files written by the crate, from the extension of `file_path` in any case.
+#[cfg(any(test, all(feature = "wasm", target_arch = "wasm32")))]
+fn mime_type<P: AsRef<Path>>(file_path: P) -> &'static str {
+    let extension = file_path
+        .as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("svg" | "svgz") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// File name of `file_path` that is safe to suggest for a download, with non UTF-8,
+/// control and reserved characters replaced by `_`.
+#[cfg(any(test, all(feature = "wasm", target_arch = "wasm32")))]
+fn download_name<P: AsRef<Path>>(file_path: P) -> String {
+    let name: String = file_path
+        .as_ref()
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | char::REPLACEMENT_CHARACTER => {
+                '_'
+            }
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // hidden files are not offered by browsers
+    let name = name.trim_start_matches(['.', ' ']);
+    if name.is_empty() {
+        "roarsvg".to_string()
+    } else {
+        name.to_string()
+    }
+}
 
 /// Write to file, WASM aware.
 ///
+/// With the `wasm` feature in wasm32 targets, the file is downloaded by the browser with the
+/// MIME type given by the extension of `file_path` and its (sanitized) file name.
+///
 /// WASM part adapted from [bevyengine/bevy#8455](/bevyengine/bevy/pull/8455).
-pub fn to_file<P: AsRef<Path>>(tree: usvg::Tree, file_path: P) -> Result<(), LyonTranslationError> {
+pub fn to_file<P: AsRef<Path>>(data: &[u8], file_path: P) -> Result<(), LyonTranslationError> {
     // simply write string to path
-    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
     {
-        use std::io::Write;
-        let mut output = std::fs::File::create::<P>(file_path)
-            .map_err(|e| LyonTranslationError::IoWrite(Box::new(e)))?;
-        write!(output, "{}", tree.to_string(&XmlOptions::default()))
+        let output = std::fs::File::create::<P>(file_path)
             .map_err(|e| LyonTranslationError::IoWrite(Box::new(e)))?;
+        to_writer(data, output)?;
     }
 
-    #[cfg(target_arch = "wasm32")]
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
     {
-        #[derive(Debug)]
-        struct WASMError(&'static str);
-
-        match (|| {
-            let file_path = file_path.as_ref().to_owned();
-            use wasm_bindgen::{JsCast, JsValue};
-            let svg = tree.to_string(&XmlOptions::default());
-            let blob = web_sys::Blob::new_with_str_sequence(&js_sys::Array::from_iter(
-                std::iter::once(JsValue::from_str(svg.as_str())),
-            ))
-            .map_err(|_| WASMError("error writing blob"))?;
+        use wasm_bindgen::JsCast;
+        let blob = to_blob(data, mime_type(&file_path))?;
+        (|| {
             let url = web_sys::Url::create_object_url_with_blob(&blob)
-                .map_err(|_| WASMError("error writing url"))?;
-            let window = web_sys::window().unwrap();
-            let document = window.document().unwrap();
+                .map_err(|_| "error writing url")?;
+            // web workers have no window nor document
+            let document = web_sys::window()
+                .and_then(|window| window.document())
+                .ok_or("no document to download from, use to_blob in workers")?;
             let link = document
                 .create_element("a")
-                .map_err(|_| WASMError("error creating <a>"))?;
+                .map_err(|_| "error creating <a>")?;
             link.set_attribute("href", &url)
-                .map_err(|_| WASMError("error creating <href>"))?;
-            link.set_attribute(
-                "download",
-                file_path
-                    .file_name()
-                    .and_then(|filename| filename.to_str())
-                    .ok_or_else(|| WASMError("Invalid filename"))?,
-            )
-            .map_err(|_| WASMError("Invalid filename"))?;
+                .map_err(|_| "error creating <href>")?;
+            link.set_attribute("download", &download_name(&file_path))
+                .map_err(|_| "Invalid filename")?;
             let html_element = link
                 .dyn_into::<web_sys::HtmlElement>()
-                .map_err(|_| WASMError("error creating <html>"))?;
+                .map_err(|_| "error creating <html>")?;
             html_element.click();
-            web_sys::Url::revoke_object_url(&url).map_err(|_| WASMError("Error revoking url"))?;
-            Ok::<(), WASMError>(())
-        })() {
-            Err(e) => return Err(LyonTranslationError::IoWrite(format!("{:?}", e).into())),
-            _ => (),
-        };
+            web_sys::Url::revoke_object_url(&url).map_err(|_| "Error revoking url")?;
+            Ok::<(), &'static str>(())
+        })()
+        .map_err(|e| LyonTranslationError::IoWrite(e.into()))?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downloads_are_typed_and_sanitized() {
+        assert_eq!(mime_type("figure.svg"), "image/svg+xml");
+        assert_eq!(mime_type("figure.PDF"), "application/pdf");
+        assert_eq!(mime_type("Figure.SVG"), "image/svg+xml");
+        assert_eq!(mime_type("figure.txt"), "application/octet-stream");
+        assert_eq!(mime_type("out/figure.png"), "image/png");
+        assert_eq!(download_name("out/fig:1?.svg"), "fig_1_.svg");
+        assert_eq!(download_name("..hidden.svg"), "hidden.svg");
+        assert_eq!(download_name("out/.."), "roarsvg");
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let name = std::ffi::OsStr::from_bytes(b"fig\xff.svg");
+            assert_eq!(download_name(name), "fig_.svg");
+        }
+    }
+}
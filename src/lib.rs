@@ -2,21 +2,60 @@
 //!
 //! It provides a struct [`LyonWriter`] that accepts a [`push`](LyonWriter::push) operation to append [`Path`]s
 //! and a [`write`](LyonWriter::write) operation to write all those paths to an SVG using [`usvg`].
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
-use lyon_path::{Event, Path};
+use lyon_path::iterator::FromPolyline;
+use lyon_path::math::Point;
+use lyon_path::{Event, Path, PathBuffer, PathEvent, PathSlice, Polygon, Position};
 
-use usvg::tiny_skia_path::{Path as PathData, PathBuilder};
+pub use usvg::filter::{CompositeOperator, MorphologyOperator};
+/// Path data as stored in the SVG tree, a re-export of [`tiny_skia_path::Path`](usvg::tiny_skia_path::Path).
+pub use usvg::tiny_skia_path::Path as PathData;
+use usvg::tiny_skia_path::PathBuilder;
+pub use usvg::{
+    AlignmentBaseline, BlendMode, Color, DominantBaseline, Fill, FillRule, FontStretch, FontStyle,
+    LineCap, LineJoin, NodeKind, Paint, PaintOrder, SpreadMethod, Stroke, TextAnchor,
+    Transform as SvgTransform, Units,
+};
 use usvg::{
-    AlignmentBaseline, AspectRatio, CharacterPosition, DominantBaseline, Font, Group,
-    ImageRendering, LengthAdjust, NodeExt, NonZeroPositiveF32, NonZeroRect, Opacity, Paint,
-    PaintOrder, Path as SvgPath, Size, TextAnchor, TextChunk, TextRendering, TextSpan,
-    TreeTextToPath, ViewBox, WritingMode,
+    AspectRatio, ClipPath, Group, ImageRendering, LinearGradient, Mask, NodeExt, NonZeroRect,
+    Opacity, Path as SvgPath, Pattern, RadialGradient, Size, TreeTextToPath, ViewBox,
 };
-pub use usvg::{Color, Fill, NodeKind, Stroke, Transform as SvgTransform};
-use usvg::{StrokeWidth, Text, Tree};
+use usvg::{StrokeWidth, Tree};
+mod effects;
+mod filter;
+mod hatch;
+mod interop;
 mod io;
+mod paint;
+#[cfg(feature = "svg2pdf")]
+mod pdf;
+mod props;
+#[cfg(feature = "raster")]
+mod raster;
+mod shapes;
+mod simplify;
+mod text;
+mod xml;
+pub use effects::{ClipId, FilterId};
+pub use filter::{FilterBuilder, FilterInput};
+pub use hatch::Hatch;
+pub use interop::PathSink;
 use io::to_file;
+#[cfg(all(feature = "save-picker", target_arch = "wasm32"))]
+use io::to_picked_file;
+#[cfg(all(feature = "flate2", not(target_arch = "wasm32")))]
+use io::to_svgz_file;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+use io::{to_blob, to_element};
+pub use paint::{fill_pattern, GradientOptions, GradientStop, PatternId};
+use props::{is_data_name, NodeMarkup};
+pub use props::{GroupProps, PathProps, StrokeBuilder};
+pub use shapes::{ArrowHead, CornerShape};
+pub use text::{FontDesc, RichText, SpanStyle};
+use xml::{data_uri, escape, path_data_to_d, use_shared_paths, Decorations, Document, Metadata};
 
 #[derive(Debug)]
 pub enum LyonTranslationError {
@@ -30,11 +69,27 @@ pub enum LyonTranslationError {
     SvgFailure,
     FontFailure,
     IoWrite(Box<dyn std::error::Error>),
+    /// A point of a path is NaN or infinite. `node` is the index that the path (or its group)
+    /// would have in the document, active layer or scene node it was pushed to.
+    NonFiniteCoordinate {
+        node: usize,
+        x: f32,
+        y: f32,
+    },
+    /// A string of SVG path data could not be parsed, with the reason.
+    InvalidPathData(String),
+    /// The name of a `data-*` attribute is not valid in SVG and HTML, with the name.
+    InvalidAttributeName(String),
+    /// The id of a node with markup (e.g., classes) is used by another node, or starts with
+    /// `roarsvg-`, which is reserved for the ids generated by the writer.
+    DuplicateId(String),
 }
 
 /// Translate from [`lyon_path::Path`] to [`usvg::Path`] on [`push`](Self::push)
 /// and [write](Self::write) an SVG to a file.
 ///
+/// The `push` family of methods return the writer, so that they can be chained.
+///
 /// # Example
 ///
 /// ```
@@ -75,9 +130,88 @@ pub enum LyonTranslationError {
 pub struct LyonWriter<T> {
     nodes: Vec<usvg::Node>,
     global_transform: Option<SvgTransform>,
+    size: Option<(f32, f32)>,
+    view_box: Option<(f32, f32, f32, f32)>,
+    /// Nodes outside of any layer, stored here while a layer is active.
+    document_nodes: Vec<usvg::Node>,
+    layers: Vec<Layer>,
+    active_layer: Option<usize>,
+    /// Group nodes of the scene graph, indexed by [`NodeId`].
+    scene: Vec<usvg::Node>,
+    /// Counter for the ids generated by the writer (e.g., for paint servers).
+    next_id: usize,
+    /// Paths waiting to be translated, if the writer is lazy.
+    lazy: Option<LazyPaths>,
+    /// Translated path data by hash, if identical paths share their data.
+    path_cache: Option<HashMap<u64, Vec<Rc<PathData>>>>,
+    /// Whether consecutive paths with the same style are written as one path.
+    merge_paths: bool,
+    /// Distance in pixels below which points of polylines are dropped.
+    decimation: Option<f32>,
+    /// Grid to which the coordinates of the paths are snapped when translated.
+    tolerance: Option<f32>,
+    /// Bounding box of the nodes outside of artboards, kept up to date while pushing, or
+    /// `None` if it has to be computed when writing (e.g., scene nodes may have moved).
+    bounds: Option<(f32, f32, f32, f32)>,
+    ordering: Ordering,
+    /// Rectangle `(x, y, width, height)` to which the whole document is clipped.
+    clip_rect: Option<(f32, f32, f32, f32)>,
+    /// Markup of the pushed nodes that [`usvg`] cannot represent, by id.
+    decorations: Decorations,
+    /// Ids given to the pushed nodes and whether the node has markup, which must not be
+    /// shared with other nodes, since it is matched by id.
+    user_ids: HashMap<String, bool>,
+    metadata: Metadata,
     fontdb: T,
 }
 
+/// Paths pushed to a lazy [`LyonWriter`], translated when the tree is built.
+///
+/// Each path has a placeholder node in its final place of the document, which is
+/// overwritten with the translated node.
+#[derive(Default)]
+struct LazyPaths {
+    paths: Vec<Path>,
+    entries: Vec<LazyEntry>,
+}
+
+/// Placeholder of a path of a lazy [`LyonWriter`] and how to write it.
+struct LazyEntry {
+    placeholder: usvg::Node,
+    props: PathProps,
+    /// Whether the path is dropped (instead of failing) if it cannot be translated.
+    droppable: bool,
+    /// Whether the path counts for the bounds of the document once translated.
+    tracked: bool,
+}
+
+/// Handle to a group node of the scene graph of a [`LyonWriter`].
+///
+/// Obtained from [`LyonWriter::add_scene_node`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// Order in which the nodes of a [`LyonWriter`] are written, see [`LyonWriter::with_ordering`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Ordering {
+    /// In the order they were pushed, so that later nodes are drawn on top of earlier ones.
+    #[default]
+    Insertion,
+    /// Paths first (unpainted, stroked, filled and then filled and stroked), followed by text,
+    /// images and groups.
+    StyleSorted,
+}
+
+/// Named layer of a [`LyonWriter`], written as an Inkscape layer.
+struct Layer {
+    name: String,
+    nodes: Vec<usvg::Node>,
+    /// Placement in the document and view box of the layer, if it is an artboard.
+    artboard: Option<(NonZeroRect, NonZeroRect)>,
+}
+
+const INKSCAPE_NS: &str = "http://www.inkscape.org/namespaces/inkscape";
+
 /// Utility function to build a [`Stroke`].
 pub fn stroke(color: Color, opacity: f32, width: f32) -> Stroke {
     Stroke {
@@ -88,6 +222,39 @@ pub fn stroke(color: Color, opacity: f32, width: f32) -> Stroke {
     }
 }
 
+/// Utility function to build a dashed [`Stroke`].
+///
+/// `dasharray` lists the lengths of alternating dashes and gaps; as in SVG, a list of odd
+/// length is repeated to yield an even one. A list with negative values or summing zero
+/// produces a solid stroke.
+pub fn stroke_dashed(
+    color: Color,
+    opacity: f32,
+    width: f32,
+    dasharray: Vec<f32>,
+    dashoffset: f32,
+) -> Stroke {
+    Stroke {
+        dasharray: normalize_dasharray(dasharray),
+        dashoffset,
+        ..stroke(color, opacity, width)
+    }
+}
+
+fn normalize_dasharray(mut dasharray: Vec<f32>) -> Option<Vec<f32>> {
+    if dasharray
+        .iter()
+        .any(|&len| !(len >= 0.0 && len.is_finite()))
+        || dasharray.iter().sum::<f32>() <= 0.0
+    {
+        return None;
+    }
+    if dasharray.len() % 2 == 1 {
+        dasharray.extend_from_within(..);
+    }
+    Some(dasharray)
+}
+
 /// Utility function to build a [`Fill`].
 pub fn fill(color: Color, opacity: f32) -> Fill {
     Fill {
@@ -97,6 +264,146 @@ pub fn fill(color: Color, opacity: f32) -> Fill {
     }
 }
 
+/// Utility function to build a [`Fill`] with the even-odd rule, so that overlapping
+/// subpaths (e.g., a ring drawn as two circles) leave holes regardless of their direction.
+pub fn fill_evenodd(color: Color, opacity: f32) -> Fill {
+    Fill {
+        rule: FillRule::EvenOdd,
+        ..fill(color, opacity)
+    }
+}
+
+/// Translate a [`Path`] to SVG path data, the value of the `d` attribute of a `<path>`,
+/// with the coordinates formatted as in the output of the writer.
+///
+/// Returns an empty string if the path has no segments.
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::path_to_d;
+/// use lyon_path::Path;
+/// use lyon_path::geom::euclid::Point2D;
+///
+/// let mut path_builder = Path::builder();
+/// path_builder.begin(Point2D::origin());
+/// path_builder.line_to(Point2D::new(3.0, 2.0));
+/// path_builder.end(false);
+/// assert_eq!(path_to_d(&path_builder.build()), "M 0 0 L 3 2");
+/// ```
+pub fn path_to_d(path: &Path) -> String {
+    lyon_path_to_usvg(path.as_slice())
+        .map(|data| path_data_to_d(&data))
+        .unwrap_or_default()
+}
+
+/// Write an SVG per layer of `document` to `dir`, named after the layers.
+fn write_layer_files(
+    document: &Document,
+    dir: &std::path::Path,
+) -> Result<Vec<std::path::PathBuf>, LyonTranslationError> {
+    let mut file_paths: Vec<std::path::PathBuf> = Vec::new();
+    for (i, (name, layer_document)) in document.layer_documents().into_iter().enumerate() {
+        let stem: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let mut file_path = dir.join(format!("{stem}.svg"));
+        if stem.is_empty() || file_paths.contains(&file_path) {
+            file_path = dir.join(format!("{stem}-{}.svg", i + 1));
+        }
+        to_file(layer_document.to_svg_string().as_bytes(), &file_path)?;
+        file_paths.push(file_path);
+    }
+    Ok(file_paths)
+}
+
+/// Transform of the content of an artboard, placing `view_box` in `rect` as an SVG viewport
+/// does by default (`preserveAspectRatio="xMidYMid meet"`).
+fn artboard_transform(rect: NonZeroRect, view_box: NonZeroRect) -> SvgTransform {
+    let scale = (rect.width() / view_box.width()).min(rect.height() / view_box.height());
+    let tx = rect.x() + (rect.width() - view_box.width() * scale) / 2.0 - view_box.x() * scale;
+    let ty = rect.y() + (rect.height() - view_box.height() * scale) / 2.0 - view_box.y() * scale;
+    SvgTransform::from_row(scale, 0.0, 0.0, scale, tx, ty)
+}
+
+/// Width and height of documents without content nor [size](LyonWriter::with_size).
+const DEFAULT_SIZE: (f32, f32) = (256.0, 256.0);
+
+/// Bounds (min x, max x, min y, max y) containing nothing, the start of [`min_an_max`] folds.
+const EMPTY_BOUNDS: (f32, f32, f32, f32) = (
+    f32::INFINITY,
+    f32::NEG_INFINITY,
+    f32::INFINITY,
+    f32::NEG_INFINITY,
+);
+
+/// Tight bounding box of `node` in the coordinates of its parent, following curves instead of
+/// their control points, transforming the paths before measuring them and including their
+/// strokes. Text is measured with `fonts` or, without them, estimated.
+fn exact_bbox(node: &usvg::Node, fonts: Option<&usvg::fontdb::Database>) -> Option<usvg::Rect> {
+    node_bbox(node, node.transform(), fonts)
+}
+
+fn node_bbox(
+    node: &usvg::Node,
+    ts: SvgTransform,
+    fonts: Option<&usvg::fontdb::Database>,
+) -> Option<usvg::Rect> {
+    match *node.borrow() {
+        NodeKind::Path(ref path) => {
+            let outline = path
+                .stroke
+                .as_ref()
+                .and_then(|stroke| path.data.stroke(&outline_stroke(stroke), 1.0));
+            let data = outline.as_ref().unwrap_or(&path.data);
+            if ts.has_skew() {
+                // the bounds of a rotated shape are not the rotated bounds of the shape
+                data.clone().transform(ts)?.compute_tight_bounds()
+            } else {
+                data.compute_tight_bounds()?.transform(ts)
+            }
+        }
+        NodeKind::Image(ref image) => image.view_box.rect.to_rect().transform(ts),
+        NodeKind::Group(_) => {
+            let (min_x, max_x, min_y, max_y) = node
+                .children()
+                .filter_map(|child| node_bbox(&child, ts.pre_concat(child.transform()), fonts))
+                .fold(EMPTY_BOUNDS, min_an_max);
+            usvg::Rect::from_ltrb(min_x, min_y, max_x, max_y)
+        }
+        NodeKind::Text(ref text) => text::text_bbox(text, ts, fonts),
+    }
+}
+
+/// Stroke for tiny-skia drawn like `stroke`, to measure the outline of a path with its caps
+/// and joins. Dashes are ignored since they only remove parts of the outline.
+fn outline_stroke(stroke: &Stroke) -> usvg::tiny_skia_path::Stroke {
+    use usvg::tiny_skia_path::{LineCap as Cap, LineJoin as Join};
+    usvg::tiny_skia_path::Stroke {
+        width: stroke.width.get(),
+        miter_limit: stroke.miterlimit.get(),
+        line_cap: match stroke.linecap {
+            usvg::LineCap::Butt => Cap::Butt,
+            usvg::LineCap::Round => Cap::Round,
+            usvg::LineCap::Square => Cap::Square,
+        },
+        line_join: match stroke.linejoin {
+            usvg::LineJoin::Miter => Join::Miter,
+            usvg::LineJoin::MiterClip => Join::MiterClip,
+            usvg::LineJoin::Round => Join::Round,
+            usvg::LineJoin::Bevel => Join::Bevel,
+        },
+        dash: None,
+    }
+}
+
 fn min_an_max(
     (min_x, max_x, min_y, max_y): (f32, f32, f32, f32),
     bound: usvg::Rect,
@@ -125,606 +432,4252 @@ fn min_an_max(
     )
 }
 
-impl<T> LyonWriter<T> {
-    /// Add a [`Path`] to the writer and translate it (eager).
-    pub fn push(
-        &mut self,
-        path: &Path,
-        fill: Option<Fill>,
-        stroke: Option<Stroke>,
-        transform: Option<SvgTransform>,
-    ) -> Result<(), LyonTranslationError> {
-        self.nodes.push(usvg::Node::new(NodeKind::Path(
-            lyon_path_to_svg_with_attributes(path, fill, stroke, transform)
-                .ok_or(LyonTranslationError::SvgFailure)?,
-        )));
-        Ok(())
-    }
+/// Sort the nodes to be written: paths first (by having fill and/or stroke), then text, images
+/// and groups.
+fn sort_nodes(nodes: &mut [usvg::Node]) {
+    use std::cmp::Ordering::*;
+    nodes.sort_unstable_by(|a, b| match (&*a.borrow(), &*b.borrow()) {
+        (NodeKind::Group(_), _) => Greater,
+        (_, NodeKind::Group(_)) => Less,
+        (NodeKind::Image(_), _) => Greater,
+        (_, NodeKind::Image(_)) => Less,
+        (NodeKind::Text(_), NodeKind::Path(_)) => Greater,
+        (NodeKind::Path(_), NodeKind::Text(_)) => Less,
+        (NodeKind::Path(p1), NodeKind::Path(p2)) => (2 * p1.fill.is_some() as u8
+            + p1.stroke.is_some() as u8)
+            .cmp(&(2 * p2.fill.is_some() as u8 + p2.stroke.is_some() as u8)),
+        _ => Equal,
+    });
+}
 
-    /// Push a node kind without any indirection.
-    ///
-    /// For writing Text, call first [`Self::add_fonts`] and call `push_text` instead.
-    pub fn push_node(&mut self, node: NodeKind) {
-        self.nodes.push(usvg::Node::new(node));
-    }
+/// Whether the paths `a` and `b` are drawn the same way, so that their data can be merged.
+fn same_style(a: &SvgPath, b: &SvgPath) -> bool {
+    let same_fill = match (&a.fill, &b.fill) {
+        (Some(f1), Some(f2)) => {
+            f1.paint == f2.paint && f1.opacity == f2.opacity && f1.rule == f2.rule
+        }
+        (None, None) => true,
+        _ => false,
+    };
+    let same_stroke = match (&a.stroke, &b.stroke) {
+        (Some(s1), Some(s2)) => {
+            s1.paint == s2.paint
+                && s1.opacity == s2.opacity
+                && s1.width.get() == s2.width.get()
+                && s1.miterlimit.get() == s2.miterlimit.get()
+                && s1.linecap == s2.linecap
+                && s1.linejoin == s2.linejoin
+                && s1.dasharray == s2.dasharray
+                && s1.dashoffset == s2.dashoffset
+        }
+        (None, None) => true,
+        _ => false,
+    };
+    same_fill
+        && same_stroke
+        && a.transform == b.transform
+        && a.paint_order == b.paint_order
+        && a.visibility == b.visibility
+        && a.rendering_mode == b.rendering_mode
+        && a.id.is_empty()
+        && b.id.is_empty()
+}
 
-    /// Push a raster image (formatted by the caller) as a PNG.
-    pub fn push_png(
-        &mut self,
-        data: &[u8],
-        transform: SvgTransform,
-        width: f32,
-        height: f32,
-    ) -> Result<(), LyonTranslationError> {
-        self.nodes.push(usvg::Node::new(create_png_node(
-            data, transform, width, height,
-        )?));
-        Ok(())
+/// Decimate the polylines of the paths under `root`, dropping the points closer than
+/// `tolerance` (in units of the view box) to the simplified polylines.
+fn decimate_paths(root: &usvg::Node, tolerance: f32) {
+    // shared path data is decimated once per scale
+    let mut decimated: HashMap<(*const PathData, u32), Rc<PathData>> = HashMap::new();
+    for node in root.descendants() {
+        if !matches!(*node.borrow(), NodeKind::Path(_)) {
+            continue;
+        }
+        let (sx, sy) = node.abs_transform().get_scale();
+        let local_tolerance = tolerance / sx.max(sy);
+        if !(local_tolerance > 0.0 && local_tolerance.is_finite()) {
+            continue;
+        }
+        if let NodeKind::Path(ref mut path) = *node.borrow_mut() {
+            path.data = decimated
+                .entry((Rc::as_ptr(&path.data), local_tolerance.to_bits()))
+                .or_insert_with(|| {
+                    simplify::decimate(&path.data, local_tolerance)
+                        .map(Rc::new)
+                        .unwrap_or_else(|| path.data.clone())
+                })
+                .clone();
+        }
     }
+}
 
-    /// Push a vector of nodes as the children of their own group (formatted by the caller).
-    ///
-    /// This is relevant for applying transforms to a set of elements.
-    pub fn push_group(
-        &mut self,
-        nodes: Vec<NodeKind>,
-        transform: SvgTransform,
-    ) -> Result<(), LyonTranslationError> {
-        let group_node = usvg::Node::new(NodeKind::Group(Group {
-            transform,
-            ..Default::default()
-        }));
-        for node in nodes {
-            group_node.append(usvg::Node::new(node))
+/// Merge the consecutive paths with the same style in every group under `root` into one.
+fn merge_path_runs(root: &usvg::Node) {
+    let groups: Vec<usvg::Node> = root
+        .descendants()
+        .filter(|node| matches!(*node.borrow(), NodeKind::Group(_)))
+        .collect();
+    for group in groups {
+        let mut child = group.first_child();
+        while let Some(first) = child {
+            let mut run = Vec::new();
+            let mut next = first.next_sibling();
+            while let Some(candidate) = next.take() {
+                let mergeable = match (&*first.borrow(), &*candidate.borrow()) {
+                    (NodeKind::Path(a), NodeKind::Path(b)) => same_style(a, b),
+                    _ => false,
+                };
+                if !mergeable {
+                    next = Some(candidate);
+                    break;
+                }
+                next = candidate.next_sibling();
+                run.push(candidate);
+            }
+            if !run.is_empty() {
+                if let NodeKind::Path(ref mut path) = *first.borrow_mut() {
+                    let mut builder = PathBuilder::new();
+                    builder.push_path(&path.data);
+                    for node in &run {
+                        if let NodeKind::Path(ref other) = *node.borrow() {
+                            builder.push_path(&other.data);
+                        }
+                    }
+                    if let Some(data) = builder.finish() {
+                        path.data = Rc::new(data);
+                    }
+                }
+                for node in run {
+                    node.detach();
+                }
+            }
+            child = next;
         }
-        self.nodes.push(group_node);
-        Ok(())
     }
+}
 
-    /// Add/replace a [`SvgTransform`], which will be applied to the whole SVG as a group.
-    pub fn with_transform(mut self, trans: SvgTransform) -> Self {
-        self.global_transform = Some(trans);
-        self
+/// Shifts the ids generated by a writer merged into another one with
+/// [`LyonWriter::push_writer`], so that they follow the ids of the other writer.
+///
+/// The definitions (clip paths, paint servers, etc.) are shifted once and kept by their
+/// original pointer, so that the nodes sharing one still share it.
+struct ShiftedIds {
+    offset: usize,
+    clip_paths: HashMap<*const ClipPath, Rc<ClipPath>>,
+    masks: HashMap<*const Mask, Rc<Mask>>,
+    filters: HashMap<*const usvg::filter::Filter, Rc<usvg::filter::Filter>>,
+    linear_gradients: HashMap<*const LinearGradient, Rc<LinearGradient>>,
+    radial_gradients: HashMap<*const RadialGradient, Rc<RadialGradient>>,
+    patterns: HashMap<*const Pattern, Rc<Pattern>>,
+}
+
+impl ShiftedIds {
+    fn new(offset: usize) -> Self {
+        Self {
+            offset,
+            clip_paths: HashMap::new(),
+            masks: HashMap::new(),
+            filters: HashMap::new(),
+            linear_gradients: HashMap::new(),
+            radial_gradients: HashMap::new(),
+            patterns: HashMap::new(),
+        }
     }
 
-    /// Build [`Tree`] before writing.
-    fn prepare(mut self) -> Result<Tree, LyonTranslationError> {
-        let match_node = |node: &usvg::Node| node.calculate_bbox();
-        // calculate dimensions
-        let (min_x, max_x, min_y, max_y) = self
-            .nodes
-            .iter()
-            .filter_map(match_node)
-            .fold((0f32, 0f32, 0f32, 0f32), min_an_max);
-        let width = if max_x - min_x > 0. {
-            max_x - min_x
-        } else {
-            256.0
-        };
-        let height = if max_y - min_y > 0. {
-            max_y - min_y
-        } else {
-            256.0
+    /// Shift `id` if it was generated by the writer, as `roarsvg-{prefix}{n}`.
+    fn id(&self, id: &mut String) {
+        let Some(rest) = id.strip_prefix("roarsvg-") else {
+            return;
         };
+        let prefix = rest.trim_end_matches(|c: char| c.is_ascii_digit());
+        if let Ok(n) = rest[prefix.len()..].parse::<usize>() {
+            *id = format!("roarsvg-{prefix}{}", n + self.offset);
+        }
+    }
 
-        // the root node of a tree must be a Group
-        let root_node = usvg::Node::new(NodeKind::Group(Group::default()));
-        // we append everything to a "real" group node
-        let group_node = usvg::Node::new(NodeKind::Group(Group {
-            transform: self.global_transform.unwrap_or_default(),
-            ..Default::default()
-        }));
+    /// Shift the ids of the nodes under `root` and of the definitions they use.
+    fn nodes(&mut self, root: &usvg::Node) {
+        for node in root.descendants() {
+            match *node.borrow_mut() {
+                NodeKind::Group(ref mut group) => {
+                    self.id(&mut group.id);
+                    group.clip_path = group.clip_path.as_ref().map(|clip| self.clip_path(clip));
+                    group.mask = group.mask.as_ref().map(|mask| self.mask(mask));
+                    group.filters = group
+                        .filters
+                        .iter()
+                        .map(|filter| {
+                            self.shared(filter, |ids| &mut ids.filters, |ids, f| ids.id(&mut f.id))
+                        })
+                        .collect();
+                }
+                NodeKind::Path(ref mut path) => {
+                    self.id(&mut path.id);
+                    self.paints(path.fill.as_mut(), path.stroke.as_mut());
+                }
+                NodeKind::Image(ref mut image) => self.id(&mut image.id),
+                NodeKind::Text(ref mut text) => {
+                    self.id(&mut text.id);
+                    let spans = text.chunks.iter_mut().flat_map(|chunk| &mut chunk.spans);
+                    for span in spans {
+                        self.paints(span.fill.as_mut(), span.stroke.as_mut());
+                        let decoration = &mut span.decoration;
+                        for style in [
+                            &mut decoration.underline,
+                            &mut decoration.overline,
+                            &mut decoration.line_through,
+                        ]
+                        .into_iter()
+                        .flatten()
+                        {
+                            self.paints(style.fill.as_mut(), style.stroke.as_mut());
+                        }
+                    }
+                }
+            }
+        }
+    }
 
-        use std::cmp::Ordering::*;
-        self.nodes
-            .sort_unstable_by(|a, b| match (&*a.borrow(), &*b.borrow()) {
-                (NodeKind::Group(_), _) => Greater,
-                (_, NodeKind::Group(_)) => Less,
-                (NodeKind::Image(_), _) => Greater,
-                (_, NodeKind::Image(_)) => Less,
-                (NodeKind::Text(_), NodeKind::Path(_)) => Greater,
-                (NodeKind::Path(_), NodeKind::Text(_)) => Less,
-                (NodeKind::Path(p1), NodeKind::Path(p2)) => (2 * p1.fill.is_some() as u8
-                    + p1.stroke.is_some() as u8)
-                    .cmp(&(2 * p2.fill.is_some() as u8 + p2.stroke.is_some() as u8)),
-                _ => Equal,
-            });
-        for path in self.nodes {
-            group_node.append(path);
+    fn paints(&mut self, fill: Option<&mut Fill>, stroke: Option<&mut Stroke>) {
+        let paints = fill
+            .map(|fill| &mut fill.paint)
+            .into_iter()
+            .chain(stroke.map(|stroke| &mut stroke.paint));
+        for paint in paints {
+            match paint {
+                Paint::Color(_) => {}
+                Paint::LinearGradient(gradient) => {
+                    *gradient = self.shared(
+                        gradient,
+                        |ids| &mut ids.linear_gradients,
+                        |ids, g| ids.id(&mut g.id),
+                    )
+                }
+                Paint::RadialGradient(gradient) => {
+                    *gradient = self.shared(
+                        gradient,
+                        |ids| &mut ids.radial_gradients,
+                        |ids, g| ids.id(&mut g.id),
+                    )
+                }
+                Paint::Pattern(pattern) => {
+                    *pattern = self.shared(
+                        pattern,
+                        |ids| &mut ids.patterns,
+                        |ids, p| {
+                            ids.id(&mut p.id);
+                            ids.nodes(&p.root);
+                        },
+                    )
+                }
+            }
         }
-        root_node.append(group_node);
+    }
 
-        Ok(Tree {
-            size: Size::from_wh(width, height).ok_or(LyonTranslationError::WrongBoundingBox {
-                min_x,
-                max_x,
-                min_y,
-                max_y,
-            })?,
-            view_box: ViewBox {
-                rect: NonZeroRect::from_ltrb(min_x, min_y, max_x, max_y).ok_or(
-                    LyonTranslationError::WrongBoundingBox {
-                        min_x,
-                        max_x,
-                        min_y,
-                        max_y,
-                    },
-                )?,
-                aspect: AspectRatio::default(),
+    fn clip_path(&mut self, clip: &Rc<ClipPath>) -> Rc<ClipPath> {
+        self.shared(
+            clip,
+            |ids| &mut ids.clip_paths,
+            |ids, clip| {
+                ids.id(&mut clip.id);
+                clip.clip_path = clip.clip_path.as_ref().map(|inner| ids.clip_path(inner));
+                ids.nodes(&clip.root);
             },
-            root: root_node,
-        })
+        )
     }
 
-    /// Loads fonts from a font database, enabling writing [`Text`] (`push_text`).
-    pub fn add_fonts<Fp: FontProvider>(self, fonts: Fp) -> LyonWriter<Option<Fp>> {
-        LyonWriter {
-            nodes: self.nodes,
-            global_transform: self.global_transform,
-            fontdb: Some(fonts),
-        }
+    fn mask(&mut self, mask: &Rc<Mask>) -> Rc<Mask> {
+        self.shared(
+            mask,
+            |ids| &mut ids.masks,
+            |ids, mask| {
+                ids.id(&mut mask.id);
+                mask.mask = mask.mask.as_ref().map(|inner| ids.mask(inner));
+                ids.nodes(&mask.root);
+            },
+        )
     }
 
-    /// Loads fonts from a font directory, building a [`FontProvider`] and enabling writing text.
-    pub fn add_fonts_dir<P: AsRef<std::path::Path>>(
-        self,
-        font_dir: P,
-    ) -> LyonWriter<Option<usvg::fontdb::Database>> {
-        let mut fonts = usvg::fontdb::Database::new();
-        fonts.load_fonts_dir(font_dir);
-        LyonWriter {
-            nodes: self.nodes,
-            global_transform: self.global_transform,
-            fontdb: Some(fonts),
+    /// Copy of `definition` shifted by `shift`, or the copy made before.
+    fn shared<D: Clone>(
+        &mut self,
+        definition: &Rc<D>,
+        copies: fn(&mut Self) -> &mut HashMap<*const D, Rc<D>>,
+        shift: fn(&mut Self, &mut D),
+    ) -> Rc<D> {
+        if let Some(copy) = copies(self).get(&Rc::as_ptr(definition)) {
+            return copy.clone();
         }
+        let mut copy = D::clone(definition);
+        shift(self, &mut copy);
+        let copy = Rc::new(copy);
+        copies(self).insert(Rc::as_ptr(definition), copy.clone());
+        copy
     }
 }
 
-/// Utility function to create [`usvg::Image`] elements.
-///
-/// If no grouping is needed, [`LyonWriter::push_png`] is recommended instead.
-pub fn create_png_node(
-    data: &[u8],
-    transform: SvgTransform,
-    width: f32,
-    height: f32,
-) -> Result<NodeKind, LyonTranslationError> {
-    Ok(NodeKind::Image(usvg::Image {
-        id: "".to_string(),
-        kind: usvg::ImageKind::PNG(std::sync::Arc::new(data.into())),
-        transform: SvgTransform::identity(),
-        visibility: usvg::Visibility::Visible,
-        view_box: ViewBox {
-            rect: NonZeroRect::from_xywh(transform.tx, transform.ty, width, height).ok_or(
-                LyonTranslationError::WrongBoundingBox {
-                    min_x: transform.tx - width / 2.,
-                    max_x: transform.tx + width / 2.,
-                    min_y: transform.ty - height / 2.,
-                    max_y: transform.ty + height / 2.,
-                },
-            )?,
-            aspect: AspectRatio::default(),
-        },
-        rendering_mode: ImageRendering::default(),
-    }))
-}
-
-/// Utility function to create [`Text`] elements.
-///
-/// If no grouping is needed, [`LyonWriter::push_text`] is recommended instead.
-pub fn create_text_node(
-    text: String,
-    transform: SvgTransform,
-    fill: Option<Fill>,
-    stroke: Option<Stroke>,
-    font_families: Vec<String>,
-    font_size: f32,
-) -> Result<NodeKind, LyonTranslationError> {
-    let text_len = text.len();
-    Ok(NodeKind::Text(Text {
-        id: "".to_string(),
-        positions: (0..text_len)
-            .map(|c| CharacterPosition {
-                x: Some(c as f32),
-                y: None,
-                dx: None,
-                dy: None,
-            })
-            .collect(),
-        rotate: Vec::new(),
-        transform,
-        rendering_mode: TextRendering::GeometricPrecision,
-        writing_mode: WritingMode::LeftToRight,
-        chunks: vec![TextChunk {
-            x: None,
-            y: None,
-            text,
-            anchor: TextAnchor::Start,
-            text_flow: usvg::TextFlow::Linear,
-            spans: vec![TextSpan {
-                start: 0,
-                end: text_len,
-                fill,
-                stroke,
-                paint_order: PaintOrder::FillAndStroke,
-                font: Font {
-                    families: font_families,
-                    style: usvg::FontStyle::Normal,
-                    stretch: usvg::FontStretch::Normal,
-                    weight: 1,
-                },
-                font_size: NonZeroPositiveF32::new(font_size)
-                    .ok_or(LyonTranslationError::FontFailure)?,
-                small_caps: false,
-                apply_kerning: false,
-                decoration: usvg::TextDecoration {
-                    underline: None,
-                    overline: None,
-                    line_through: None,
+impl<T> LyonWriter<T> {
+    /// Add a [`Path`] to the writer and translate it (eager, unless the writer is
+    /// [lazy](Self::with_lazy_translation)).
+    pub fn push(
+        &mut self,
+        path: &Path,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        if self.lazy.is_some() {
+            return self.push_with(
+                path,
+                PathProps {
+                    fill,
+                    stroke,
+                    transform,
+                    ..Default::default()
                 },
-                baseline_shift: Vec::new(),
-                letter_spacing: 0.0,
-                word_spacing: 0.0,
-                text_length: None,
-                length_adjust: LengthAdjust::SpacingAndGlyphs,
-                visibility: usvg::Visibility::Visible,
-                dominant_baseline: DominantBaseline::Auto,
-                alignment_baseline: AlignmentBaseline::Auto,
-            }],
-        }],
-    }))
-}
-/// Marker struct for [`LyonWriter`] that indicates that no [`Text`] node has been added
-/// so far. It disallows `push_text` and does not convert [`Text`] to [`SvgPath`] upon write.
-pub struct NoText;
-
-impl LyonWriter<NoText> {
-    pub fn new() -> LyonWriter<NoText> {
-        LyonWriter {
-            nodes: Vec::new(),
-            global_transform: None,
-            fontdb: NoText,
+            );
         }
+        check_finite(path.as_slice(), self.nodes.len())?;
+        let data = self.translate(path.as_slice())?;
+        self.add(usvg::Node::new(NodeKind::Path(svg_path_with_attributes(
+            data, fill, stroke, transform,
+        ))));
+        Ok(self)
     }
 
-    /// Write the contained [`Path`]s to an SVG at `file_path`. Text will NOT be written!
-    pub fn write<P: AsRef<std::path::Path>>(
-        self,
-        file_path: P,
-    ) -> Result<(), LyonTranslationError> {
-        let tree = self.prepare()?;
-        to_file(tree, file_path)?;
-        Ok(())
-    }
-
-    /// Loads fonts from a font file, building a [`FontProvider`] and enabling writing text.
-    pub fn add_fonts_source(
-        self,
-        font_source: &[u8],
-    ) -> LyonWriter<Option<usvg::fontdb::Database>> {
-        let mut fonts = usvg::fontdb::Database::new();
-        fonts.load_font_data(font_source.to_vec());
-        LyonWriter {
-            nodes: self.nodes,
-            global_transform: self.global_transform,
-            fontdb: Some(fonts),
+    /// Add a [`Path`] to the writer with all the options in [`PathProps`] and translate it
+    /// (eager, unless the writer is [lazy](Self::with_lazy_translation)).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, PathProps, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(Point2D::new(1.0, 1.0));
+    /// path_builder.line_to(Point2D::new(2.0, 0.0));
+    /// path_builder.end(true);
+    /// let path = path_builder.build();
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_with(&path, PathProps::new().fill(fill(Color::black(), 1.0)).id("node1"))
+    ///     .expect("Path should be writable!");
+    /// ```
+    pub fn push_with(
+        &mut self,
+        path: &Path,
+        props: PathProps,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        check_finite(path.as_slice(), self.nodes.len())?;
+        let mut props = props;
+        self.decorate(&mut props.id, &props.markup)?;
+        let tracked = !self.in_artboard();
+        if let Some(lazy) = self.lazy.as_mut() {
+            let placeholder = usvg::Node::new(NodeKind::Group(Group::default()));
+            lazy.paths.push(path.clone());
+            lazy.entries.push(LazyEntry {
+                placeholder: placeholder.clone(),
+                props,
+                droppable: false,
+                tracked,
+            });
+            self.nodes.push(placeholder);
+            return Ok(self);
         }
+        let data = self.translate(path.as_slice())?;
+        let node = props.into_node(data);
+        self.add(node);
+        Ok(self)
     }
-}
 
-impl Default for LyonWriter<NoText> {
-    fn default() -> Self {
-        Self::new()
+    /// Add a path given as a stream of [`PathEvent`]s, such as the output of a tessellator or
+    /// a custom builder, without building a [`Path`] first.
+    ///
+    /// The events are translated as they are consumed, even if the writer is
+    /// [lazy](Self::with_lazy_translation).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::PathEvent;
+    /// use lyon_path::math::point;
+    ///
+    /// let (first, last) = (point(0.0, 0.0), point(2.0, 1.0));
+    /// let events = [
+    ///     PathEvent::Begin { at: first },
+    ///     PathEvent::Line { from: first, to: last },
+    ///     PathEvent::End { last, first, close: true },
+    /// ];
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_events(events, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 2 1 Z\""));
+    /// ```
+    pub fn push_events<I: IntoIterator<Item = PathEvent>>(
+        &mut self,
+        events: I,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let mut non_finite = None;
+        let events = events.into_iter().inspect(|event| {
+            if non_finite.is_none() {
+                non_finite = non_finite_point(event);
+            }
+        });
+        let data = events_to_usvg(events, PathBuilder::new());
+        if let Some(point) = non_finite {
+            return Err(LyonTranslationError::NonFiniteCoordinate {
+                node: self.nodes.len(),
+                x: point.x,
+                y: point.y,
+            });
+        }
+        let data = data.ok_or(LyonTranslationError::SvgFailure)?;
+        self.push_skia_path(data, fill, stroke, transform)
     }
-}
 
-/// Marker trait that changes the behavior of `write` for [`LyonWriter`]
-/// and allows for writing text to the SVG.
-pub trait FontProvider {
-    fn get_fontdb(self) -> usvg::fontdb::Database;
-}
-impl FontProvider for usvg::fontdb::Database {
-    fn get_fontdb(self) -> usvg::fontdb::Database {
-        self
+    /// Start a path that is built with lyon's [`PathBuilder`](lyon_path::traits::PathBuilder)
+    /// trait (or [`SvgPathBuilder`](lyon_path::traits::SvgPathBuilder), through
+    /// `with_svg`) and translated on the fly, without allocating a [`Path`]. The path is
+    /// added once the sink is [built](lyon_path::traits::Build::build).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::math::point;
+    /// use lyon_path::traits::{Build, PathBuilder, SvgPathBuilder};
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// let mut sink = writer
+    ///     .path_sink(Some(fill(Color::black(), 1.0)), None, None)
+    ///     .with_svg();
+    /// sink.move_to(point(0.0, 0.0));
+    /// sink.line_to(point(2.0, 1.0));
+    /// sink.close();
+    /// sink.build().expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 2 1 Z\""));
+    /// ```
+    pub fn path_sink(
+        &mut self,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> PathSink<'_, T> {
+        PathSink::new(self, fill, stroke, transform)
     }
-}
 
-/// Implemented for `Option<T>` to be able to ergonomically take it without cloning.
-impl<T: FontProvider> LyonWriter<Option<T>> {
-    /// Add [`Text`] to the writer, filling it as an unique [`TextChunk`] whose
-    /// [`TextSpan`] style applies to all the text.
+    /// Add a path built with [`Path::builder_with_attributes`] as one SVG path per subpath,
+    /// styled by `style` from the custom attributes of the first endpoint of the subpath.
     ///
-    /// Requires having called [`LyonWriter::add_fonts`] beforehand.
+    /// The subpaths are translated eagerly, even if the writer is
+    /// [lazy](Self::with_lazy_translation).
     ///
     /// # Example
     ///
     /// ```
-    /// use roarsvg::{Color, LyonWriter, SvgTransform, fill, stroke};
+    /// use roarsvg::{Color, LyonWriter, fill};
     /// use lyon_path::Path;
-    /// use lyon_path::geom::euclid::Point2D;
+    /// use lyon_path::math::point;
     ///
-    /// let file_path = "text.svg";
+    /// // the attribute is the index of the color of each subpath
+    /// let mut builder = Path::builder_with_attributes(1);
+    /// builder.begin(point(0.0, 0.0), &[0.0]);
+    /// builder.line_to(point(1.0, 1.0), &[0.0]);
+    /// builder.end(false);
+    /// builder.begin(point(2.0, 0.0), &[1.0]);
+    /// builder.line_to(point(3.0, 1.0), &[1.0]);
+    /// builder.end(false);
+    /// let path = builder.build();
+    /// let colors = [Color::black(), Color::new_rgb(255, 0, 0)];
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_attributed(
+    ///         &path,
+    ///         |attributes| (Some(fill(colors[attributes[0] as usize], 1.0)), None),
+    ///         None,
+    ///     )
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Paths should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 1 1\""));
+    /// assert!(svg.contains("fill=\"#ff0000\""));
+    /// ```
+    pub fn push_attributed<F>(
+        &mut self,
+        path: &Path,
+        mut style: F,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError>
+    where
+        F: FnMut(&[f32]) -> (Option<Fill>, Option<Stroke>),
+    {
+        check_finite(path.as_slice(), self.nodes.len())?;
+        let mut subpaths = Vec::new();
+        let mut events = Vec::new();
+        let mut attributes: &[f32] = &[];
+        for event in path.iter_with_attributes() {
+            if let Event::Begin { at: (_, begin) } = event {
+                attributes = begin;
+            }
+            events.push(event.with_points());
+            if let Event::End { .. } = event {
+                // subpaths without segments are not written
+                if let Some(data) = events_to_usvg(events.drain(..), PathBuilder::new()) {
+                    subpaths.push((attributes, data));
+                }
+            }
+        }
+        for (attributes, data) in subpaths {
+            let (fill, stroke) = style(attributes);
+            self.push_skia_path(data, fill, stroke, transform)?;
+        }
+        Ok(self)
+    }
+
+    /// Add a path given as the `d` attribute of an SVG `<path>`, such as path data received
+    /// from other services. Arcs are converted to curves.
     ///
-    /// let writer = LyonWriter::new();
-    /// let mut fontdb = usvg::fontdb::Database::new();
-    /// fontdb.load_system_fonts();
-    /// let mut writer = writer.add_fonts(fontdb);
-    /// // first we add a Path, if not, the ViewBox calculation will panic!
-    /// // this is a caveat and should be fixed in the future
-    /// let mut path_builder = Path::builder();
-    /// path_builder.begin(Point2D::origin());
-    /// path_builder.line_to(
-    ///     Point2D::new(3.0, 2.0),
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, LyonTranslationError, fill};
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_svg_d("M0 0 l10 10 h-10 z", Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Path data should be valid!");
+    /// assert!(matches!(
+    ///     writer.push_svg_d("M0 0 L", None, None, None),
+    ///     Err(LyonTranslationError::InvalidPathData(_))
+    /// ));
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 10 10 L 0 10 Z\""));
+    /// ```
+    pub fn push_svg_d(
+        &mut self,
+        d: &str,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = interop::svg_d_to_usvg(d, self.nodes.len())?;
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add a [`kurbo::Shape`], such as a `BezPath` or a `Circle`, for geometry coming from the
+    /// kurbo ecosystem. Arcs are converted to curves within `1e-3` units.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use kurbo::{BezPath, Circle};
+    ///
+    /// let mut path = BezPath::new();
+    /// path.move_to((0.0, 0.0));
+    /// path.quad_to((1.0, 1.0), (2.0, 0.0));
+    /// path.close_path();
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_kurbo(&path, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Path should be writable!");
+    /// writer
+    ///     .push_kurbo(&Circle::new((5.0, 5.0), 2.0), None, None, None)
+    ///     .expect("Circle should be writable!");
+    /// let svg = writer.to_svg_string().expect("Paths should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 Q 1 1 2 0 Z\""));
+    /// ```
+    #[cfg(feature = "kurbo")]
+    pub fn push_kurbo(
+        &mut self,
+        shape: &impl kurbo::Shape,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = interop::kurbo_to_usvg(shape, self.nodes.len())?;
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add a [`geo_types::Geometry`], such as a `Polygon`, a `MultiLineString` or a
+    /// `MultiPolygon` of the geo crates, as a single path.
+    ///
+    /// Polygons are filled with the [`FillRule::EvenOdd`] rule, so that their interior rings
+    /// are holes, whatever their orientation. Points have no outline and are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use geo_types::{LineString, Polygon};
+    ///
+    /// let square = Polygon::new(
+    ///     LineString::from(vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]),
+    ///     vec![LineString::from(vec![(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)])],
     /// );
-    /// path_builder.end(true);
+    /// let mut writer = LyonWriter::new();
     /// writer
-    ///     .push(
-    ///         &path_builder.build(),
-    ///         None,
-    ///         Some(stroke(Color::black(), 1.0, 1.0)),
-    ///         Some(SvgTransform::from_translate(2.0, 2.0)),
+    ///     .push_geo(square, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Polygon should be writable!");
+    /// let svg = writer.to_svg_string().expect("Polygon should be writable!");
+    /// assert!(svg.contains("fill-rule=\"evenodd\""));
+    /// assert!(svg.contains("d=\"M 0 0 L 4 0 L 4 4 L 0 4 Z M 1 1 L 3 1 L 3 3 L 1 3 Z\""));
+    /// ```
+    #[cfg(feature = "geo")]
+    pub fn push_geo<C: geo_types::CoordNum + Into<f64>>(
+        &mut self,
+        geometry: impl Into<geo_types::Geometry<C>>,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = interop::geo_to_usvg(&geometry.into(), self.nodes.len())?;
+        let fill = fill.map(|fill| Fill {
+            rule: FillRule::EvenOdd,
+            ..fill
+        });
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add the mesh that a [lyon tessellator](lyon_tessellation) produced, so that GPU
+    /// pipelines can export vector graphics. The boundary of the triangles is written as a
+    /// single path, filled with the [`FillRule::NonZero`] rule.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::math::{point, Point};
+    /// use lyon_tessellation::{BuffersBuilder, FillOptions, FillTessellator, VertexBuffers};
+    ///
+    /// let mut builder = Path::builder();
+    /// builder.add_rectangle(
+    ///     &lyon_path::math::Box2D::new(point(0.0, 0.0), point(4.0, 4.0)),
+    ///     lyon_path::Winding::Positive,
+    /// );
+    /// builder.add_rectangle(
+    ///     &lyon_path::math::Box2D::new(point(1.0, 1.0), point(3.0, 3.0)),
+    ///     lyon_path::Winding::Negative,
+    /// );
+    /// let path = builder.build();
+    /// let mut mesh: VertexBuffers<Point, u16> = VertexBuffers::new();
+    /// FillTessellator::new()
+    ///     .tessellate_path(
+    ///         &path,
+    ///         &FillOptions::default(),
+    ///         &mut BuffersBuilder::new(&mut mesh, |vertex: lyon_tessellation::FillVertex| {
+    ///             vertex.position()
+    ///         }),
     ///     )
-    ///     .expect("Path 1 should be writable!");
+    ///     .expect("Path should be tessellated!");
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_mesh(&mesh, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Mesh should be writable!");
+    /// let svg = writer.to_svg_string().expect("Mesh should be writable!");
+    /// // the outer square and the hole, instead of triangles
+    /// assert_eq!(svg.matches('M').count(), 2);
+    /// ```
+    #[cfg(feature = "tessellation")]
+    pub fn push_mesh<V: lyon_path::Position, I: Copy + Into<u32>>(
+        &mut self,
+        buffers: &lyon_tessellation::VertexBuffers<V, I>,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = interop::mesh_to_usvg(buffers, self.nodes.len())?;
+        let fill = fill.map(|fill| Fill {
+            rule: FillRule::NonZero,
+            ..fill
+        });
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add a tiny-skia path, as used by resvg and its rasterizers, skipping the translation
+    /// from lyon. The path is quantized and shared if configured.
     ///
-    /// // push the created path with some fill and stroke, in the origin
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use usvg::tiny_skia_path::{PathBuilder, Rect};
+    ///
+    /// let rect = Rect::from_xywh(0.0, 0.0, 4.0, 2.0).unwrap();
+    /// let mut writer = LyonWriter::new();
     /// writer
-    ///     .push_text(
-    ///         "hello".to_string(),
-    ///         vec!["Arial".to_string()],
-    ///         12.0,
-    ///         SvgTransform::from_translate(0., 0.),
-    ///         Some(fill(usvg::Color::black(), 1.0)),
-    ///         Some(stroke(usvg::Color::black(), 1.0, 1.0)),
+    ///     .push_skia_path(PathBuilder::from_rect(rect), Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 4 0 L 4 2 L 0 2 Z\""));
+    /// ```
+    pub fn push_skia_path(
+        &mut self,
+        data: PathData,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = apply_tolerance(data, self.tolerance).ok_or(LyonTranslationError::SvgFailure)?;
+        let data = self.share_data(data);
+        self.add(usvg::Node::new(NodeKind::Path(svg_path_with_attributes(
+            data, fill, stroke, transform,
+        ))));
+        Ok(self)
+    }
+
+    /// Add a [`Polygon`], closed or not as given by its `closed` field, without building a
+    /// [`Path`] first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill, stroke};
+    /// use lyon_path::Polygon;
+    /// use lyon_path::math::point;
+    ///
+    /// let points = [point(0.0, 0.0), point(2.0, 0.0), point(1.0, 2.0)];
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_polygon(
+    ///         &Polygon { points: &points, closed: true },
+    ///         Some(fill(Color::black(), 1.0)),
+    ///         None,
+    ///         None,
     ///     )
-    ///     .expect("Text should be writable!");
-    /// let mut path_builder = Path::builder();
-    /// // finally, write the SVG, Text with be converted to SvgPath
-    /// writer.write(file_path).expect("Writing should not panic!");
+    ///     .expect("Polygon should be writable!");
+    /// writer
+    ///     .push_polyline(points, None, Some(stroke(Color::black(), 1.0, 0.1)), None)
+    ///     .expect("Polyline should be writable!");
+    /// let svg = writer.to_svg_string().expect("Paths should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 2 0 L 1 2 Z\""));
+    /// assert!(svg.contains("d=\"M 0 0 L 2 0 L 1 2\""));
+    /// ```
+    pub fn push_polygon(
+        &mut self,
+        polygon: &Polygon<Point>,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        self.push_events(polygon.path_events(), fill, stroke, transform)
+    }
+
+    /// Add an open polyline through `points`, which can be [`Point`]s, `(x, y)` tuples or
+    /// `[x, y]` arrays, given by value or by reference (e.g., from a slice), without building a
+    /// [`Path`] first.
+    ///
+    /// # Example
     ///
-    /// # std::fs::remove_file(&file_path).unwrap();
     /// ```
-    pub fn push_text(
+    /// use roarsvg::{Color, LyonWriter, fill, stroke};
+    ///
+    /// let points: Vec<(f32, f32)> = vec![(0.0, 0.0), (2.0, 0.0), (1.0, 2.0)];
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_polyline(&points, None, Some(stroke(Color::black(), 1.0, 0.1)), None)
+    ///     .expect("Polyline should be writable!");
+    /// writer
+    ///     .push_closed_polygon(&points[1..], Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Polygon should be writable!");
+    /// let svg = writer.to_svg_string().expect("Paths should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 2 0 L 1 2\""));
+    /// assert!(svg.contains("d=\"M 2 0 L 1 2 Z\""));
+    /// ```
+    pub fn push_polyline(
         &mut self,
-        text: String,
-        font_families: Vec<String>,
-        font_size: f32,
-        transform: SvgTransform,
+        points: impl IntoIterator<Item = impl Position>,
         fill: Option<Fill>,
         stroke: Option<Stroke>,
-    ) -> Result<(), LyonTranslationError> {
-        self.nodes.push(usvg::Node::new(create_text_node(
-            text,
-            transform,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        self.push_events(
+            FromPolyline::open(points.into_iter().map(|p| p.position())),
             fill,
             stroke,
-            font_families,
-            font_size,
-        )?));
-        Ok(())
+            transform,
+        )
     }
 
-    /// Loads fonts from a font file, building a [`FontProvider`] if needed and enabling writing text.
-    pub fn add_fonts_source(
-        self,
-        font_source: &[u8],
-    ) -> LyonWriter<Option<usvg::fontdb::Database>> {
-        let mut fonts = self.fontdb.map(|f| f.get_fontdb()).unwrap_or_default();
-        fonts.load_font_data(font_source.to_vec());
-        LyonWriter {
-            nodes: self.nodes,
-            global_transform: self.global_transform,
-            fontdb: Some(fonts),
-        }
+    /// Add a closed polygon through `points`, taken like in
+    /// [`push_polyline`](Self::push_polyline).
+    pub fn push_closed_polygon(
+        &mut self,
+        points: impl IntoIterator<Item = impl Position>,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        self.push_events(
+            FromPolyline::closed(points.into_iter().map(|p| p.position())),
+            fill,
+            stroke,
+            transform,
+        )
     }
 
-    /// Write the contained [`Path`]s to an SVG at `file_path`, converting all [`Text`] nodes
-    /// to paths.
-    pub fn write<P: AsRef<std::path::Path>>(
-        mut self,
-        file_path: P,
-    ) -> Result<(), LyonTranslationError> {
-        let fontdb = self
-            .fontdb
-            .take()
-            .ok_or(LyonTranslationError::NoFonts)?
-            .get_fontdb();
-        let mut tree = self.prepare()?;
-        tree.convert_text(&fontdb);
-        to_file(tree, file_path)?;
-        Ok(())
+    /// Add many [`Path`]s sharing the same style and transform, translating them in a
+    /// single pass (eager).
+    ///
+    /// If any path cannot be translated, none of them is added.
+    pub fn push_many<'a>(
+        &mut self,
+        paths: impl IntoIterator<Item = &'a Path>,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        self.push_slices(
+            paths.into_iter().map(Path::as_slice),
+            fill,
+            stroke,
+            transform,
+        )
     }
-}
 
-fn lyon_path_to_svg_with_attributes(
-    path: &Path,
-    fill: Option<Fill>,
-    stroke: Option<Stroke>,
-    transform: Option<SvgTransform>,
-) -> Option<SvgPath> {
-    let mut op = SvgPath::new(Rc::new(lyon_path_to_usvg(path)?));
-    op.fill = fill;
-    op.stroke = stroke;
-    if let Some(trans) = transform {
-        op.transform = trans;
+    /// Add a [`PathSlice`], such as one of the paths of a [`PathBuffer`], translating it
+    /// (eager) without copying it into its own [`Path`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::PathBuffer;
+    /// use lyon_path::math::point;
+    ///
+    /// let mut buffer = PathBuffer::new();
+    /// for i in 0..3 {
+    ///     let mut builder = buffer.builder();
+    ///     builder.begin(point(i as f32, 0.0));
+    ///     builder.line_to(point(i as f32 + 1.0, 1.0));
+    ///     builder.end(true);
+    ///     builder.build();
+    /// }
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_slice(buffer.get(1), Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Path should be writable!");
+    /// // or all of them at once
+    /// writer
+    ///     .push_buffer(&buffer, None, None, None)
+    ///     .expect("Paths should be writable!");
+    /// let svg = writer.to_svg_string().expect("Paths should be writable!");
+    /// assert_eq!(svg.matches("<path").count(), 4);
+    /// ```
+    pub fn push_slice(
+        &mut self,
+        path: PathSlice,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        check_finite(path, self.nodes.len())?;
+        let data = self.translate(path)?;
+        self.add(usvg::Node::new(NodeKind::Path(svg_path_with_attributes(
+            data, fill, stroke, transform,
+        ))));
+        Ok(self)
     }
-    Some(op)
-}
 
-fn lyon_path_to_usvg(path: &Path) -> Option<PathData> {
-    let mut upath_builder = PathBuilder::new();
-    let mut current = None;
-    for event in path.iter() {
-        match event {
-            Event::Begin { at } => {
-                current = Some(at);
-                upath_builder.move_to(at.x, at.y)
+    /// Add all the paths stored in `buffer`, sharing the same style and transform, as
+    /// [`push_many`](Self::push_many) does.
+    pub fn push_buffer(
+        &mut self,
+        buffer: &PathBuffer,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        self.push_slices(buffer.iter(), fill, stroke, transform)
+    }
+
+    /// Translate all `paths` before adding any of them, as in [`push_many`](Self::push_many).
+    fn push_slices<'a>(
+        &mut self,
+        paths: impl IntoIterator<Item = PathSlice<'a>>,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let mut nodes = Vec::new();
+        for path in paths {
+            check_finite(path, self.nodes.len() + nodes.len())?;
+            let mut svg_path = SvgPath::new(self.translate(path)?);
+            svg_path.fill = fill.clone();
+            svg_path.stroke = stroke.clone();
+            svg_path.transform = transform.unwrap_or_default();
+            nodes.push(usvg::Node::new(NodeKind::Path(svg_path)));
+        }
+        for node in nodes {
+            self.add(node);
+        }
+        Ok(self)
+    }
+
+    /// Reserve room for at least `additional` more nodes in the document (or active layer).
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+        if let Some(lazy) = self.lazy.as_mut() {
+            lazy.paths.reserve(additional);
+            lazy.entries.reserve(additional);
+        }
+    }
+
+    /// Push a node kind without any indirection.
+    ///
+    /// For writing Text, call first [`Self::add_fonts`] and call `push_text` instead.
+    pub fn push_node(&mut self, node: NodeKind) -> &mut Self {
+        self.add(usvg::Node::new(node));
+        self
+    }
+
+    /// Push a raster image (formatted by the caller) as a PNG.
+    pub fn push_png(
+        &mut self,
+        data: &[u8],
+        transform: SvgTransform,
+        width: f32,
+        height: f32,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let node = usvg::Node::new(create_png_node(data, transform, width, height)?);
+        self.add(node);
+        Ok(self)
+    }
+
+    /// Push a vector of nodes as the children of their own group (formatted by the caller).
+    ///
+    /// This is relevant for applying transforms to a set of elements.
+    pub fn push_group(
+        &mut self,
+        nodes: Vec<NodeKind>,
+        transform: SvgTransform,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let group_node = usvg::Node::new(NodeKind::Group(Group {
+            transform,
+            ..Default::default()
+        }));
+        for node in nodes {
+            group_node.append(usvg::Node::new(node))
+        }
+        self.add(group_node);
+        Ok(self)
+    }
+
+    /// Push a set of [`Path`]s as the children of their own group, using `fill` and `stroke`
+    /// for all of them.
+    ///
+    /// The group is written as a `<g>` element with the given `transform` and, optionally, `id`,
+    /// which fails with [`LyonTranslationError::DuplicateId`] if it is reserved or used by a
+    /// node with markup.
+    pub fn push_path_group<'a>(
+        &mut self,
+        paths: impl IntoIterator<Item = &'a Path>,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: SvgTransform,
+        mut id: Option<String>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        self.decorate(&mut id, &NodeMarkup::default())?;
+        let group_node = usvg::Node::new(NodeKind::Group(Group {
+            id: id.unwrap_or_default(),
+            transform,
+            ..Default::default()
+        }));
+        for path in paths {
+            check_finite(path.as_slice(), self.nodes.len())?;
+            let data = self.translate(path.as_slice())?;
+            group_node.append(usvg::Node::new(NodeKind::Path(svg_path_with_attributes(
+                data,
+                fill.clone(),
+                stroke.clone(),
+                None,
+            ))));
+        }
+        self.add(group_node);
+        Ok(self)
+    }
+
+    /// Push all the nodes of another writer as the children of their own group.
+    ///
+    /// The global transform of `writer`, if any, is applied after `transform`. The nodes
+    /// of its layers are added to the group too, after the rest. As in
+    /// [`push_path_group`](Self::push_path_group), the `id` of the group is checked.
+    ///
+    /// The ids generated by `writer` (e.g., of its clip paths and gradients) are renumbered
+    /// to follow the ones of this writer, so that they do not collide. The markup of its
+    /// nodes (e.g., classes) is kept, failing with [`LyonTranslationError::DuplicateId`]
+    /// if the id of one of them is used by a node of this writer.
+    pub fn push_writer<U>(
+        &mut self,
+        mut writer: LyonWriter<U>,
+        transform: SvgTransform,
+        mut id: Option<String>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        self.decorate(&mut id, &NodeMarkup::default())?;
+        // the ids of both writers are checked as if their nodes were pushed to one
+        for (user_id, &has_markup) in &writer.user_ids {
+            if self
+                .user_ids
+                .get(user_id)
+                .is_some_and(|&other_has_markup| other_has_markup || has_markup)
+            {
+                return Err(LyonTranslationError::DuplicateId(user_id.clone()));
             }
-            Event::Line { from, to } => {
-                if let Some(current_point) = current {
-                    if from != current_point {
-                        upath_builder.move_to(from.x, from.y);
+        }
+        let group_node = usvg::Node::new(NodeKind::Group(Group {
+            id: id.unwrap_or_default(),
+            transform: transform.pre_concat(writer.global_transform.unwrap_or_default()),
+            ..Default::default()
+        }));
+        writer.translate_lazy()?;
+        writer.switch_nodes(None);
+        // the generated ids of `writer` follow the ones of `self`
+        let mut shifted = ShiftedIds::new(self.next_id);
+        self.next_id += writer.next_id;
+        let layer_nodes = writer.layers.into_iter().flat_map(|layer| layer.nodes);
+        for node in writer.nodes.into_iter().chain(layer_nodes) {
+            shifted.nodes(&node);
+            group_node.append(node)
+        }
+        self.decorations
+            .merge(writer.decorations, |decorated| shifted.id(decorated));
+        for (user_id, has_markup) in writer.user_ids {
+            *self.user_ids.entry(user_id).or_default() |= has_markup;
+        }
+        self.add(group_node);
+        Ok(self)
+    }
+
+    /// Build a nested group with a closure.
+    ///
+    /// Every node pushed to the writer received by `build` lands inside the new group, which
+    /// can in turn contain other groups.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, SvgTransform, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(Point2D::new(1.0, 1.0));
+    /// path_builder.line_to(Point2D::new(2.0, 0.0));
+    /// path_builder.end(true);
+    /// let path = path_builder.build();
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .group(SvgTransform::from_translate(10.0, 0.0), |g| {
+    ///         g.push(&path, Some(fill(Color::black(), 1.0)), None, None)?;
+    ///         g.group(SvgTransform::from_scale(2.0, 2.0), |inner| {
+    ///             inner.push(&path, None, None, None)?;
+    ///             Ok(())
+    ///         })?;
+    ///         Ok(())
+    ///     })
+    ///     .expect("Group should be writable!");
+    /// ```
+    pub fn group<F>(
+        &mut self,
+        transform: SvgTransform,
+        build: F,
+    ) -> Result<&mut Self, LyonTranslationError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), LyonTranslationError>,
+    {
+        self.group_with(GroupProps::new().transform(transform), build)
+    }
+
+    /// Same as [`LyonWriter::group`] with the given [`GroupProps`], e.g., to clip the group
+    /// (see [`LyonWriter::define_clip`]).
+    pub fn group_with<F>(
+        &mut self,
+        props: GroupProps,
+        build: F,
+    ) -> Result<&mut Self, LyonTranslationError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), LyonTranslationError>,
+    {
+        let outer_nodes = std::mem::take(&mut self.nodes);
+        let outer_bounds = self.bounds;
+        let lazy_paths = self.lazy.as_ref().map(|lazy| lazy.entries.len());
+        let result = build(self);
+        let inner_nodes = std::mem::replace(&mut self.nodes, outer_nodes);
+        // the inner nodes were tracked without the transform of the group and lazy paths
+        // cannot be tracked once they are nested
+        let inner_lazy_paths = self.lazy.as_ref().map(|lazy| lazy.entries.len()) != lazy_paths;
+        if self.bounds.is_some() && !inner_lazy_paths {
+            self.bounds = outer_bounds;
+        } else {
+            self.bounds = None;
+        }
+        result?;
+        let mut props = props;
+        self.decorate(&mut props.id, &props.markup)?;
+        let group_node = usvg::Node::new(NodeKind::Group(props.into_group()));
+        for node in inner_nodes {
+            group_node.append(node)
+        }
+        self.add(group_node);
+        Ok(self)
+    }
+
+    /// Add a group node to the scene graph, as a child of `parent` or, if `None`, at the top
+    /// level of the document (or active layer).
+    ///
+    /// The `transform` of a node is composed with those of its ancestors, so moving a parent
+    /// moves all its descendants.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, SvgTransform, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(Point2D::new(1.0, 1.0));
+    /// path_builder.line_to(Point2D::new(2.0, 0.0));
+    /// path_builder.end(true);
+    /// let path = path_builder.build();
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// let body = writer.add_scene_node(None, SvgTransform::from_translate(10.0, 0.0));
+    /// let arm = writer.add_scene_node(Some(body), SvgTransform::from_translate(0.0, 5.0));
+    /// writer
+    ///     .push_child(arm, &path, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Path should be writable!");
+    /// // the arm follows the body
+    /// writer.set_scene_transform(body, SvgTransform::from_translate(20.0, 0.0));
+    /// assert_eq!(
+    ///     writer.world_transform(arm),
+    ///     SvgTransform::from_translate(20.0, 5.0)
+    /// );
+    /// ```
+    pub fn add_scene_node(&mut self, parent: Option<NodeId>, transform: SvgTransform) -> NodeId {
+        let node = usvg::Node::new(NodeKind::Group(Group {
+            transform,
+            ..Default::default()
+        }));
+        match parent {
+            Some(parent) => self.scene[parent.0].append(node.clone()),
+            None => self.nodes.push(node.clone()),
+        }
+        // the scene can change after being pushed
+        self.bounds = None;
+        self.scene.push(node);
+        NodeId(self.scene.len() - 1)
+    }
+
+    /// Add a [`Path`] as a child of the scene node `parent`.
+    ///
+    /// # Panics
+    ///
+    /// If `parent` was not created by this writer.
+    pub fn push_child(
+        &mut self,
+        parent: NodeId,
+        path: &Path,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        check_finite(path.as_slice(), self.scene[parent.0].children().count())?;
+        let data = self.translate(path.as_slice())?;
+        self.scene[parent.0].append(usvg::Node::new(NodeKind::Path(svg_path_with_attributes(
+            data, fill, stroke, transform,
+        ))));
+        Ok(self)
+    }
+
+    /// Add a node kind as a child of the scene node `parent`, without any indirection.
+    ///
+    /// # Panics
+    ///
+    /// If `parent` was not created by this writer.
+    pub fn push_child_node(&mut self, parent: NodeId, node: NodeKind) -> &mut Self {
+        self.scene[parent.0].append(usvg::Node::new(node));
+        self
+    }
+
+    /// Replace the transform of the scene node `id`, relative to its parent.
+    ///
+    /// # Panics
+    ///
+    /// If `id` was not created by this writer.
+    pub fn set_scene_transform(&mut self, id: NodeId, transform: SvgTransform) {
+        if let NodeKind::Group(ref mut group) = *self.scene[id.0].borrow_mut() {
+            group.transform = transform;
+        }
+    }
+
+    /// Transform of the scene node `id` composed with all its ancestors and the global
+    /// transform of the writer.
+    ///
+    /// # Panics
+    ///
+    /// If `id` was not created by this writer.
+    pub fn world_transform(&self, id: NodeId) -> SvgTransform {
+        self.global_transform
+            .unwrap_or_default()
+            .pre_concat(self.scene[id.0].abs_transform())
+    }
+
+    /// Defer the translation of the paths added with [`push`](Self::push),
+    /// [`push_with`](Self::push_with) and [`extend`](Extend::extend) until the SVG is built.
+    ///
+    /// The writer stores the [`Path`]s with their style instead, so that all of them are
+    /// translated in a single pass when writing (in parallel, with the `rayon` feature).
+    /// Paths that cannot be translated make writing fail with
+    /// [`LyonTranslationError::SvgFailure`] instead of the push.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(Point2D::new(1.0, 1.0));
+    /// path_builder.line_to(Point2D::new(2.0, 0.0));
+    /// path_builder.end(true);
+    /// let path = path_builder.build();
+    ///
+    /// let mut writer = LyonWriter::new().with_lazy_translation();
+    /// writer
+    ///     .push(&path, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Lazy pushes only store the path");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("<path"));
+    /// ```
+    pub fn with_lazy_translation(mut self) -> Self {
+        self.lazy.get_or_insert_with(LazyPaths::default);
+        self
+    }
+
+    /// Translate the paths stored by a lazy writer, overwriting their placeholders.
+    fn translate_lazy(&mut self) -> Result<(), LyonTranslationError> {
+        let Some(lazy) = self.lazy.as_mut() else {
+            return Ok(());
+        };
+        let LazyPaths { paths, entries } = std::mem::take(lazy);
+        let tolerance = self.tolerance;
+        // nodes are not thread-safe, so only the path data is translated in parallel
+        #[cfg(feature = "rayon")]
+        let data: Vec<Option<PathData>> = {
+            use rayon::prelude::*;
+            paths
+                .par_iter()
+                .map(|path| translate_with_tolerance(path.as_slice(), tolerance))
+                .collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let data = paths
+            .iter()
+            .map(|path| translate_with_tolerance(path.as_slice(), tolerance));
+        let mut dropped = Vec::new();
+        for (data, entry) in data.into_iter().zip(entries) {
+            let LazyEntry {
+                placeholder,
+                props,
+                droppable,
+                tracked,
+            } = entry;
+            match data {
+                Some(data) => {
+                    let node = props.into_node(self.share_data(data));
+                    *placeholder.borrow_mut() = node.borrow().clone();
+                    for child in node.children().collect::<Vec<_>>() {
+                        child.detach();
+                        placeholder.append(child);
                     }
-                }
-                upath_builder.line_to(to.x, to.y);
-                current = Some(to)
-            }
-            Event::Quadratic { from, ctrl, to } => {
-                if let Some(current_point) = current {
-                    if from != current_point {
-                        upath_builder.move_to(from.x, from.y);
+                    // nested placeholders are tracked by their group, if possible
+                    if tracked && placeholder.parent().is_none() {
+                        self.track(&placeholder);
                     }
                 }
-                // TODO: check if ctrl is that one
-                upath_builder.quad_to(ctrl.x, ctrl.y, to.x, to.y);
-                current = Some(to)
-            }
-            Event::Cubic {
-                from,
-                ctrl1,
-                ctrl2,
-                to,
-            } => {
-                if let Some(current_point) = current {
-                    if from != current_point {
-                        upath_builder.move_to(from.x, from.y);
+                None if droppable => {
+                    // placeholders at the top level have no parent to be detached from
+                    if placeholder.parent().is_some() {
+                        placeholder.detach();
+                    } else {
+                        dropped.push(placeholder);
                     }
                 }
-                // TODO: check if ctrl is that one
-                upath_builder.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y);
-                current = Some(to)
+                None => return Err(LyonTranslationError::SvgFailure),
             }
-            Event::End { last, first, close } => {
-                if let Some(current_point) = current {
-                    if last != current_point {
-                        upath_builder.move_to(last.x, last.y);
-                    }
-                }
-                if close {
-                    upath_builder.line_to(first.x, first.y);
-                    upath_builder.close();
-                }
-                current = Some(last)
+        }
+        if !dropped.is_empty() {
+            let nodes = std::iter::once(&mut self.nodes)
+                .chain(std::iter::once(&mut self.document_nodes))
+                .chain(self.layers.iter_mut().map(|layer| &mut layer.nodes));
+            for nodes in nodes {
+                nodes.retain(|node| !dropped.contains(node));
             }
         }
+        Ok(())
+    }
+
+    /// Share the data of identical paths, such as a marker pushed many times at different
+    /// transforms, instead of storing a copy per path.
+    ///
+    /// Paths with the same data are written as `<use>` elements of a single `<path>`
+    /// definition, which keeps their style and transform.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, SvgTransform, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(Point2D::new(1.0, 1.0));
+    /// path_builder.line_to(Point2D::new(2.0, 0.0));
+    /// path_builder.end(true);
+    /// let marker = path_builder.build();
+    ///
+    /// let mut writer = LyonWriter::new().with_shared_paths();
+    /// for i in 0..100 {
+    ///     let transform = SvgTransform::from_translate(i as f32 * 3.0, 0.0);
+    ///     writer
+    ///         .push(&marker, Some(fill(Color::black(), 1.0)), None, Some(transform))
+    ///         .expect("Path should be writable!");
+    /// }
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert_eq!(svg.matches("<use").count(), 100);
+    /// ```
+    pub fn with_shared_paths(mut self) -> Self {
+        self.path_cache.get_or_insert_with(HashMap::new);
+        self
+    }
+
+    /// Set the order in which the top-level nodes of the document and of each layer are
+    /// written. By default, it is the order in which they were pushed ([`Ordering::Insertion`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, Ordering, fill, stroke};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(Point2D::new(1.0, 1.0));
+    /// path_builder.line_to(Point2D::new(2.0, 0.0));
+    /// path_builder.end(true);
+    /// let path = path_builder.build();
+    ///
+    /// let mut writer = LyonWriter::new().with_ordering(Ordering::StyleSorted);
+    /// writer
+    ///     .push(&path, Some(fill(Color::white(), 1.0)), None, None)
+    ///     .expect("Path should be writable!");
+    /// writer
+    ///     .push(&path, None, Some(stroke(Color::black(), 1.0, 0.1)), None)
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// // the stroked path is moved below the filled one
+    /// assert!(svg.find("stroke=\"#000000\"").unwrap() < svg.find("fill=\"#ffffff\"").unwrap());
+    /// ```
+    pub fn with_ordering(mut self, ordering: Ordering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Write consecutive paths with the same fill, stroke and transform (and no id) as a
+    /// single `<path>`, whose data is the concatenation of theirs.
+    ///
+    /// It shrinks documents with many small shapes, such as the points of a scatter plot.
+    /// Overlapping paths are then drawn as a whole: translucent overlaps are not darker and,
+    /// with [`FillRule::EvenOdd`] (or opposite windings), they leave holes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut writer = LyonWriter::new().with_merged_paths();
+    /// for i in 0..100 {
+    ///     let mut path_builder = Path::builder();
+    ///     path_builder.begin(Point2D::new(i as f32, 0.0));
+    ///     path_builder.line_to(Point2D::new(i as f32 + 0.5, 1.0));
+    ///     path_builder.line_to(Point2D::new(i as f32 + 1.0, 0.0));
+    ///     path_builder.end(true);
+    ///     writer
+    ///         .push(&path_builder.build(), Some(fill(Color::black(), 1.0)), None, None)
+    ///         .expect("Path should be writable!");
+    /// }
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert_eq!(svg.matches("<path").count(), 1);
+    /// ```
+    pub fn with_merged_paths(mut self) -> Self {
+        self.merge_paths = true;
+        self
+    }
+
+    /// Simplify polylines when writing, dropping the points that are less than `pixels` away
+    /// from the simplified line once the SVG is displayed at its size.
+    ///
+    /// Dense traces (e.g., from sensors) with far more points than pixels shrink without a
+    /// visible change. Only runs of line segments are simplified; curves are kept as they are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, stroke};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// for i in 1..10_000 {
+    ///     let x = i as f32 / 100.0;
+    ///     path_builder.line_to(Point2D::new(x, x.sin()));
+    /// }
+    /// path_builder.end(false);
+    /// let mut writer = LyonWriter::new().with_size(400.0, 100.0).with_decimation(0.5);
+    /// writer
+    ///     .push(&path_builder.build(), None, Some(stroke(Color::black(), 1.0, 0.1)), None)
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.matches('L').count() < 1_000);
+    /// ```
+    pub fn with_decimation(mut self, pixels: f32) -> Self {
+        self.decimation = Some(pixels);
+        self
+    }
+
+    /// Snap the coordinates of the paths to a grid of `tolerance` when they are translated,
+    /// drawing curves that deviate less than `tolerance` from a line as lines and dropping the
+    /// segments that collapse into a point.
+    ///
+    /// The written coordinates are shorter and, since it happens before storing the paths,
+    /// more of them are shared with [`LyonWriter::with_shared_paths`] or decimated with
+    /// [`LyonWriter::with_decimation`]. Non-positive or non-finite tolerances are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, stroke};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::new(0.1234, 0.0));
+    /// path_builder.line_to(Point2D::new(3.14159, 2.71828));
+    /// path_builder.end(false);
+    /// let mut writer = LyonWriter::new().with_tolerance(0.5);
+    /// writer
+    ///     .push(&path_builder.build(), None, Some(stroke(Color::black(), 1.0, 0.1)), None)
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 3 2.5\""));
+    /// ```
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = (tolerance > 0.0 && tolerance.is_finite()).then_some(tolerance);
+        self
+    }
+
+    /// Translate `path`, sharing its data with an identical path if possible.
+    fn translate(&mut self, path: PathSlice) -> Result<Rc<PathData>, LyonTranslationError> {
+        let data = translate_with_tolerance(path, self.tolerance)
+            .ok_or(LyonTranslationError::SvgFailure)?;
+        Ok(self.share_data(data))
+    }
+
+    /// Reuse the data of an identical path, if paths are shared.
+    fn share_data(&mut self, data: PathData) -> Rc<PathData> {
+        let Some(cache) = self.path_cache.as_mut() else {
+            return Rc::new(data);
+        };
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for verb in data.verbs() {
+            (*verb as u8).hash(&mut hasher);
+        }
+        for point in data.points() {
+            point.x.to_bits().hash(&mut hasher);
+            point.y.to_bits().hash(&mut hasher);
+        }
+        let candidates = cache.entry(hasher.finish()).or_default();
+        if let Some(shared) = candidates.iter().find(|shared| ***shared == data) {
+            return shared.clone();
+        }
+        let data = Rc::new(data);
+        candidates.push(data.clone());
+        data
+    }
+
+    /// Add/replace a [`SvgTransform`], which will be applied to the whole SVG as a group.
+    pub fn with_transform(mut self, trans: SvgTransform) -> Self {
+        self.global_transform = Some(trans);
+        self
+    }
+
+    /// Set the `width` and `height` of the SVG instead of deriving them from the view box.
+    ///
+    /// Without content, the view box covers the size too, so an empty writer is written as a
+    /// blank SVG of this size (256 × 256 by default).
+    ///
+    /// Non-positive dimensions make writing fail with [`LyonTranslationError::WrongBoundingBox`].
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Set the `viewBox` of the SVG instead of computing it from the bounding box of the nodes.
+    ///
+    /// If no size is set with [`with_size`](Self::with_size), the size of the SVG is the
+    /// size of the view box. Non-positive dimensions make writing fail with
+    /// [`LyonTranslationError::WrongBoundingBox`].
+    pub fn with_view_box(mut self, x: f32, y: f32, width: f32, height: f32) -> Self {
+        self.view_box = Some((x, y, width, height));
+        self
+    }
+
+    /// Set the title of the document, written as its `<title>` (shown by browsers as the
+    /// name of the tab) and in its Dublin Core `<metadata>`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::LyonWriter;
+    ///
+    /// let writer = LyonWriter::new()
+    ///     .with_title("Glycolysis")
+    ///     .with_description("Central carbon metabolism of E. coli")
+    ///     .with_creator("Jane Doe")
+    ///     .with_license("https://creativecommons.org/licenses/by/4.0/");
+    /// let svg = writer.to_svg_string().expect("Empty SVG should be writable!");
+    /// assert!(svg.contains("<title>Glycolysis</title>"));
+    /// assert!(svg.contains("<dc:creator><cc:Agent><dc:title>Jane Doe</dc:title></cc:Agent></dc:creator>"));
+    /// ```
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.metadata.title = Some(title.into());
+        self
+    }
+
+    /// Set the description of the document, written as its `<desc>` and in its metadata.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.metadata.description = Some(description.into());
+        self
+    }
+
+    /// Set the author of the document, written in its metadata.
+    pub fn with_creator(mut self, creator: impl Into<String>) -> Self {
+        self.metadata.creator = Some(creator.into());
+        self
+    }
+
+    /// Set the license of the document, written in its metadata: as a link if it is a URL
+    /// (e.g., of a Creative Commons license), or as the rights statement otherwise.
+    pub fn with_license(mut self, license: impl Into<String>) -> Self {
+        self.metadata.license = Some(license.into());
+        self
+    }
+
+    /// Clip the whole document to the rectangle `(x, y, width, height)`, in the coordinates
+    /// of the nodes (before the [global transform](Self::with_transform)).
+    ///
+    /// Unless a view box is set, the SVG covers the content inside the rectangle. To clip
+    /// only some nodes, see [`define_clip_rect`](Self::define_clip_rect).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::math::point;
+    ///
+    /// let mut writer = LyonWriter::new().with_clip_rect(0.0, 0.0, 2.0, 1.0);
+    /// writer
+    ///     .push_circle(point(0.0, 0.0), 4.0, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Circle should have an area!");
+    /// let svg = writer.to_svg_string().expect("Circle should be writable!");
+    /// assert!(svg.contains("viewBox=\"0 0 2 1\""));
+    /// assert!(svg.contains("<g clip-path=\"url(#roarsvg-clip1)\">"));
+    /// ```
+    pub fn with_clip_rect(mut self, x: f32, y: f32, width: f32, height: f32) -> Self {
+        self.clip_rect = Some((x, y, width, height));
+        self
+    }
+
+    /// Direct subsequent pushes to the layer called `name`, creating it if needed.
+    ///
+    /// Layers are written after the nodes that do not belong to any layer, in order of
+    /// creation, as groups that Inkscape recognizes as layers. Layers should not be switched
+    /// inside [`group`](Self::group).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(Point2D::new(1.0, 1.0));
+    /// path_builder.line_to(Point2D::new(2.0, 0.0));
+    /// path_builder.end(true);
+    /// let path = path_builder.build();
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer.layer("shapes");
+    /// writer
+    ///     .push(&path, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Path should be writable!");
+    /// writer.layer("annotations");
+    /// // ... push the annotations
+    /// writer.exit_layer();
+    /// ```
+    pub fn layer(&mut self, name: &str) {
+        let index = match self.layers.iter().position(|layer| layer.name == name) {
+            Some(index) => index,
+            None => {
+                self.layers.push(Layer {
+                    name: name.to_string(),
+                    nodes: Vec::new(),
+                    artboard: None,
+                });
+                self.layers.len() - 1
+            }
+        };
+        self.switch_nodes(Some(index));
+    }
+
+    /// Direct subsequent pushes to the artboard `name`, creating it if needed.
+    ///
+    /// An artboard is a layer written as a nested `<svg>` viewport that places its own
+    /// coordinate system, given by `view_box` as `(x, y, width, height)`, in the `rect` of
+    /// the document (scaled uniformly and centered); its content is clipped to the viewport.
+    /// It is useful to lay out small multiples or panels, drawing each one in its own units.
+    /// Calling it again with the same `name` updates the placement of the artboard.
+    ///
+    /// Artboards are written to their own files by [`LyonWriter::write_layers`].
+    ///
+    /// # Errors
+    ///
+    /// [`LyonTranslationError::WrongBoundingBox`] if `rect` or `view_box` have no area.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(Point2D::new(1.0, 1.0));
+    /// path_builder.line_to(Point2D::new(2.0, 0.0));
+    /// path_builder.end(true);
+    /// let path = path_builder.build();
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// for (i, panel) in ["a", "b", "c"].iter().enumerate() {
+    ///     writer
+    ///         .artboard(panel, (i as f32 * 100.0, 0.0, 100.0, 50.0), (0.0, 0.0, 2.0, 1.0))
+    ///         .expect("Artboard should have an area!");
+    ///     writer
+    ///         .push(&path, Some(fill(Color::black(), 1.0)), None, None)
+    ///         .expect("Path should be writable!");
+    /// }
+    /// writer.exit_layer();
+    /// ```
+    pub fn artboard(
+        &mut self,
+        name: &str,
+        rect: (f32, f32, f32, f32),
+        view_box: (f32, f32, f32, f32),
+    ) -> Result<(), LyonTranslationError> {
+        let to_rect = |(x, y, width, height): (f32, f32, f32, f32)| {
+            NonZeroRect::from_xywh(x, y, width, height).ok_or(
+                LyonTranslationError::WrongBoundingBox {
+                    min_x: x,
+                    max_x: x + width,
+                    min_y: y,
+                    max_y: y + height,
+                },
+            )
+        };
+        let artboard = Some((to_rect(rect)?, to_rect(view_box)?));
+        self.layer(name);
+        if let Some(index) = self.active_layer {
+            self.layers[index].artboard = artboard;
+        }
+        Ok(())
+    }
+
+    /// Direct subsequent pushes to the document itself, outside of any layer.
+    pub fn exit_layer(&mut self) {
+        self.switch_nodes(None);
+    }
+
+    /// Store the nodes being pushed in their layer and load the ones of `layer`.
+    fn switch_nodes(&mut self, layer: Option<usize>) {
+        if layer == self.active_layer {
+            return;
+        }
+        let incoming = match layer {
+            Some(index) => std::mem::take(&mut self.layers[index].nodes),
+            None => std::mem::take(&mut self.document_nodes),
+        };
+        let outgoing = std::mem::replace(&mut self.nodes, incoming);
+        match self.active_layer {
+            Some(index) => self.layers[index].nodes = outgoing,
+            None => self.document_nodes = outgoing,
+        }
+        self.active_layer = layer;
+    }
+
+    /// Bounds of the content of the document, including artboards, once the global transform
+    /// is applied.
+    fn content_bounds(&self, fonts: Option<&usvg::fontdb::Database>) -> (f32, f32, f32, f32) {
+        let global = self.global_transform.unwrap_or_default();
+        let artboards = self
+            .layers
+            .iter()
+            .filter_map(|layer| Some(layer.artboard?.0.to_rect()));
+        match self.bounds {
+            // the tracked bounds can be transformed as a whole, unless they are rotated
+            Some(bounds) if !global.has_skew() => {
+                let (min_x, max_x, min_y, max_y) = artboards.fold(bounds, min_an_max);
+                usvg::Rect::from_ltrb(min_x, min_y, max_x, max_y)
+                    .and_then(|rect| rect.transform(global))
+                    .map_or(EMPTY_BOUNDS, |rect| min_an_max(EMPTY_BOUNDS, rect))
+            }
+            _ => self
+                .nodes
+                .iter()
+                .chain(
+                    self.layers
+                        .iter()
+                        .filter(|layer| layer.artboard.is_none())
+                        .flat_map(|layer| layer.nodes.iter()),
+                )
+                .filter_map(|node| node_bbox(node, global.pre_concat(node.transform()), fonts))
+                .chain(artboards.filter_map(|rect| rect.transform(global)))
+                .fold(EMPTY_BOUNDS, min_an_max),
+        }
+    }
+
+    /// Push `node` to the document (or active layer), growing the bounds of the document.
+    fn add(&mut self, node: usvg::Node) {
+        if !self.in_artboard() {
+            self.track(&node);
+        }
+        self.nodes.push(node);
+    }
+
+    /// Grow the bounds of the document to contain `node`, unless they are not tracked.
+    fn track(&mut self, node: &usvg::Node) {
+        // text is measured when writing, with the fonts that might be added later
+        let has_text = node
+            .descendants()
+            .any(|node| matches!(*node.borrow(), NodeKind::Text(_)));
+        if has_text {
+            self.bounds = None;
+        }
+        if let (Some(bounds), Some(bbox)) = (self.bounds, exact_bbox(node, None)) {
+            self.bounds = Some(min_an_max(bounds, bbox));
+        }
+    }
+
+    /// Whether the active layer is an artboard, whose nodes do not size the document.
+    fn in_artboard(&self) -> bool {
+        self.active_layer
+            .is_some_and(|index| self.layers[index].artboard.is_some())
+    }
+
+    /// Generate an unique id for an element of the document.
+    fn fresh_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("roarsvg-{prefix}{}", self.next_id)
+    }
+
+    /// Write the `markup` of a node on its element, which is matched by `id`. Nodes without
+    /// an id get one, which is removed from the output.
+    ///
+    /// Fails if the id of the node is reserved or, when either node has markup, if it is
+    /// shared with another node, since the markup would be written on both.
+    fn decorate(
+        &mut self,
+        id: &mut Option<String>,
+        markup: &NodeMarkup,
+    ) -> Result<(), LyonTranslationError> {
+        if let Some((name, _)) = markup.data.iter().find(|(name, _)| !is_data_name(name)) {
+            return Err(LyonTranslationError::InvalidAttributeName(name.clone()));
+        }
+        if let Some(id) = id.as_ref() {
+            let clashes = match self.user_ids.get(id) {
+                Some(&has_markup) => has_markup || !markup.is_empty(),
+                None => id.starts_with("roarsvg-"),
+            };
+            if clashes {
+                return Err(LyonTranslationError::DuplicateId(id.clone()));
+            }
+            self.user_ids.insert(id.clone(), !markup.is_empty());
+        }
+        if markup.is_empty() {
+            return Ok(());
+        }
+        let generated = id.is_none();
+        let id = id.get_or_insert_with(|| self.fresh_id("node"));
+        let decoration = self.decorations.element(id);
+        if generated {
+            decoration.removed_attributes.push("id".to_string());
+        }
+        if let Some((href, target)) = &markup.link {
+            decoration.rename = Some("a".to_string());
+            decoration
+                .attributes
+                .push(("href".to_string(), href.clone()));
+            if let Some(target) = target {
+                decoration
+                    .attributes
+                    .push(("target".to_string(), target.clone()));
+            }
+        }
+        if !markup.classes.is_empty() {
+            decoration
+                .attributes
+                .push(("class".to_string(), markup.classes.join(" ")));
+        }
+        if let Some(title) = &markup.title {
+            decoration
+                .children
+                .push(format!("<title>{}</title>", escape(title)));
+        }
+        if let Some(desc) = &markup.desc {
+            decoration
+                .children
+                .push(format!("<desc>{}</desc>", escape(desc)));
+        }
+        for (name, value) in &markup.data {
+            decoration
+                .attributes
+                .push((format!("data-{name}"), value.clone()));
+        }
+        Ok(())
+    }
+
+    /// Change the font provider, keeping everything else.
+    fn with_fontdb<U>(self, fontdb: U) -> LyonWriter<U> {
+        LyonWriter {
+            nodes: self.nodes,
+            global_transform: self.global_transform,
+            size: self.size,
+            view_box: self.view_box,
+            document_nodes: self.document_nodes,
+            layers: self.layers,
+            active_layer: self.active_layer,
+            scene: self.scene,
+            next_id: self.next_id,
+            lazy: self.lazy,
+            path_cache: self.path_cache,
+            merge_paths: self.merge_paths,
+            decimation: self.decimation,
+            tolerance: self.tolerance,
+            bounds: self.bounds,
+            ordering: self.ordering,
+            clip_rect: self.clip_rect,
+            decorations: self.decorations,
+            user_ids: self.user_ids,
+            metadata: self.metadata,
+            fontdb,
+        }
+    }
+
+    /// Build the [`Tree`] that would be written, to post-process it or feed it to other
+    /// tools (e.g., `resvg`).
+    ///
+    /// [`Text`](usvg::Text) nodes are kept as is; they can be converted to paths with
+    /// [`usvg::TreeTextToPath::convert_text`]. Markup that [`usvg`] cannot represent, such as
+    /// the Inkscape attributes of layers, is not part of the tree.
+    pub fn into_tree(self) -> Result<Tree, LyonTranslationError> {
+        Ok(self.prepare()?.tree)
+    }
+
+    /// Build [`Tree`] before writing.
+    fn prepare(self) -> Result<Document, LyonTranslationError> {
+        self.prepare_with_fonts(None)
+    }
+
+    /// Build [`Tree`] before writing, measuring the text with `fonts` to size the document.
+    fn prepare_with_fonts(
+        mut self,
+        fonts: Option<&usvg::fontdb::Database>,
+    ) -> Result<Document, LyonTranslationError> {
+        self.translate_lazy()?;
+        self.switch_nodes(None);
+        let clip = self
+            .clip_rect
+            .map(|rect| self.define_clip_rect(rect))
+            .transpose()?;
+        let mut content_bounds = self.content_bounds(fonts);
+        if let Some((x, y, width, height)) = self.clip_rect {
+            let clip_bounds = usvg::Rect::from_xywh(x, y, width, height)
+                .and_then(|rect| rect.transform(self.global_transform.unwrap_or_default()))
+                .map(|rect| (rect.left(), rect.right(), rect.top(), rect.bottom()));
+            if let Some((min_x, max_x, min_y, max_y)) = clip_bounds {
+                let (x0, x1, y0, y1) = content_bounds;
+                content_bounds = (x0.max(min_x), x1.min(max_x), y0.max(min_y), y1.min(max_y));
+                if content_bounds.0 > content_bounds.1 || content_bounds.2 > content_bounds.3 {
+                    content_bounds = EMPTY_BOUNDS;
+                }
+            }
+        }
+        // calculate dimensions, unless provided by the caller
+        let (min_x, max_x, min_y, max_y) = match self.view_box {
+            Some((x, y, width, height)) => (x, x + width, y, y + height),
+            None => match content_bounds {
+                // an empty document is a blank canvas of the size of the SVG
+                EMPTY_BOUNDS => {
+                    let (width, height) = self.size.unwrap_or(DEFAULT_SIZE);
+                    (0., width, 0., height)
+                }
+                (min_x, max_x, min_y, max_y) => {
+                    // flat content (e.g., a horizontal line) still needs some room
+                    let pad = |min: f32, max: f32| {
+                        if max > min {
+                            (min, max)
+                        } else {
+                            (min - 0.5, max + 0.5)
+                        }
+                    };
+                    let (min_x, max_x) = pad(min_x, max_x);
+                    let (min_y, max_y) = pad(min_y, max_y);
+                    (min_x, max_x, min_y, max_y)
+                }
+            },
+        };
+        let (width, height) = self.size.unwrap_or((max_x - min_x, max_y - min_y));
+
+        // the root node of a tree must be a Group
+        let root_node = usvg::Node::new(NodeKind::Group(Group::default()));
+        // we append everything to a "real" group node
+        let group_node = usvg::Node::new(NodeKind::Group(Group {
+            transform: self.global_transform.unwrap_or_default(),
+            clip_path: clip.map(|clip| clip.0),
+            ..Default::default()
+        }));
+        let mut decorations = std::mem::take(&mut self.decorations);
+        self.metadata.decorate(&mut decorations);
+
+        if self.ordering == Ordering::StyleSorted {
+            sort_nodes(&mut self.nodes);
+        }
+        for path in self.nodes {
+            group_node.append(path);
+        }
+        let mut layers = Vec::with_capacity(self.layers.len());
+        for (i, mut layer) in self.layers.into_iter().enumerate() {
+            let id = format!("roarsvg-layer{}", i + 1);
+            layers.push((layer.name.clone(), id.clone()));
+            let layer_node = usvg::Node::new(NodeKind::Group(Group {
+                id: id.clone(),
+                transform: layer
+                    .artboard
+                    .map(|(rect, view_box)| artboard_transform(rect, view_box))
+                    .unwrap_or_default(),
+                ..Default::default()
+            }));
+            if self.ordering == Ordering::StyleSorted {
+                sort_nodes(&mut layer.nodes);
+            }
+            for node in layer.nodes {
+                layer_node.append(node);
+            }
+            group_node.append(layer_node);
+            if let Some((rect, view_box)) = layer.artboard {
+                // the transform of the group is the one of the viewport
+                let decoration = decorations.element(&id);
+                decoration.rename = Some("svg".to_string());
+                decoration.removed_attributes.push("transform".to_string());
+                decoration.attributes.extend([
+                    ("x".to_string(), rect.x().to_string()),
+                    ("y".to_string(), rect.y().to_string()),
+                    ("width".to_string(), rect.width().to_string()),
+                    ("height".to_string(), rect.height().to_string()),
+                    (
+                        "viewBox".to_string(),
+                        format!(
+                            "{} {} {} {}",
+                            view_box.x(),
+                            view_box.y(),
+                            view_box.width(),
+                            view_box.height()
+                        ),
+                    ),
+                ]);
+                continue;
+            }
+            decorations.root_attribute("xmlns:inkscape", INKSCAPE_NS);
+            let decoration = decorations.element(&id);
+            decoration
+                .attributes
+                .push(("inkscape:groupmode".to_string(), "layer".to_string()));
+            decoration
+                .attributes
+                .push(("inkscape:label".to_string(), layer.name));
+        }
+        if let Some(pixels) = self.decimation {
+            let units_per_pixel = ((max_x - min_x) / width).max((max_y - min_y) / height);
+            decimate_paths(&group_node, pixels * units_per_pixel);
+        }
+        if self.merge_paths {
+            merge_path_runs(&group_node);
+        }
+        if self.path_cache.is_some() {
+            use_shared_paths(&group_node, &mut decorations);
+        }
+        root_node.append(group_node);
+
+        let tree = Tree {
+            size: Size::from_wh(width, height).ok_or(LyonTranslationError::WrongBoundingBox {
+                min_x: 0.,
+                max_x: width,
+                min_y: 0.,
+                max_y: height,
+            })?,
+            view_box: ViewBox {
+                rect: NonZeroRect::from_ltrb(min_x, min_y, max_x, max_y).ok_or(
+                    LyonTranslationError::WrongBoundingBox {
+                        min_x,
+                        max_x,
+                        min_y,
+                        max_y,
+                    },
+                )?,
+                aspect: AspectRatio::default(),
+            },
+            root: root_node,
+        };
+        Ok(Document {
+            tree,
+            decorations,
+            layers,
+        })
+    }
+
+    /// Loads fonts from a font database, enabling writing [`Text`](usvg::Text) (`push_text`).
+    pub fn add_fonts<Fp: FontProvider>(self, fonts: Fp) -> LyonWriter<Option<Fp>> {
+        self.with_fontdb(Some(fonts))
+    }
+
+    /// Loads fonts from a font directory, building a [`FontProvider`] and enabling writing text.
+    pub fn add_fonts_dir<P: AsRef<std::path::Path>>(
+        self,
+        font_dir: P,
+    ) -> LyonWriter<Option<usvg::fontdb::Database>> {
+        let mut fonts = usvg::fontdb::Database::new();
+        fonts.load_fonts_dir(font_dir);
+        self.with_fontdb(Some(fonts))
+    }
+}
+
+/// Utility function to create [`usvg::Image`] elements.
+///
+/// If no grouping is needed, [`LyonWriter::push_png`] is recommended instead.
+pub fn create_png_node(
+    data: &[u8],
+    transform: SvgTransform,
+    width: f32,
+    height: f32,
+) -> Result<NodeKind, LyonTranslationError> {
+    Ok(NodeKind::Image(usvg::Image {
+        id: "".to_string(),
+        kind: usvg::ImageKind::PNG(std::sync::Arc::new(data.into())),
+        transform: SvgTransform::identity(),
+        visibility: usvg::Visibility::Visible,
+        view_box: ViewBox {
+            rect: NonZeroRect::from_xywh(transform.tx, transform.ty, width, height).ok_or(
+                // the translation is the top-left corner of the image, not its center
+                LyonTranslationError::WrongBoundingBox {
+                    min_x: transform.tx,
+                    max_x: transform.tx + width,
+                    min_y: transform.ty,
+                    max_y: transform.ty + height,
+                },
+            )?,
+            aspect: AspectRatio::default(),
+        },
+        rendering_mode: ImageRendering::default(),
+    }))
+}
+
+/// Utility function to create [`Text`](usvg::Text) elements.
+///
+/// If no grouping is needed, [`LyonWriter::push_text`] is recommended instead.
+pub fn create_text_node(
+    text: String,
+    transform: SvgTransform,
+    fill: Option<Fill>,
+    stroke: Option<Stroke>,
+    font_families: Vec<String>,
+    font_size: f32,
+) -> Result<NodeKind, LyonTranslationError> {
+    let mut style = SpanStyle::new(font_families, font_size);
+    style = match fill {
+        Some(fill) => style.fill(fill),
+        None => style.no_fill(),
+    };
+    if let Some(stroke) = stroke {
+        style = style.stroke(stroke);
+    }
+    RichText::new()
+        .span(text, style)
+        .transform(transform)
+        .into_node()
+}
+
+/// Marker struct for [`LyonWriter`] that indicates that no [`Text`](usvg::Text) node has been added
+/// so far. It disallows `push_text` and does not convert [`Text`](usvg::Text) to [`SvgPath`] upon write.
+pub struct NoText;
+
+impl LyonWriter<NoText> {
+    pub fn new() -> LyonWriter<NoText> {
+        LyonWriter {
+            nodes: Vec::new(),
+            global_transform: None,
+            size: None,
+            view_box: None,
+            document_nodes: Vec::new(),
+            layers: Vec::new(),
+            active_layer: None,
+            scene: Vec::new(),
+            next_id: 0,
+            lazy: None,
+            path_cache: None,
+            merge_paths: false,
+            decimation: None,
+            tolerance: None,
+            bounds: Some(EMPTY_BOUNDS),
+            ordering: Ordering::Insertion,
+            clip_rect: None,
+            decorations: Decorations::default(),
+            user_ids: HashMap::new(),
+            metadata: Metadata::default(),
+            fontdb: NoText,
+        }
+    }
+
+    /// Create a writer with room for `capacity` nodes, to avoid reallocations when the
+    /// number of pushes is known beforehand.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut writer = LyonWriter::with_capacity(1000);
+    /// for i in 0..1000 {
+    ///     let mut path_builder = Path::builder();
+    ///     path_builder.begin(Point2D::new(i as f32, 0.0));
+    ///     path_builder.line_to(Point2D::new(i as f32, 1.0));
+    ///     path_builder.end(false);
+    ///     writer
+    ///         .push(&path_builder.build(), None, None, None)
+    ///         .expect("Path should be writable!");
+    /// }
+    /// ```
+    pub fn with_capacity(capacity: usize) -> LyonWriter<NoText> {
+        let mut writer = Self::new();
+        writer.reserve(capacity);
+        writer
+    }
+
+    /// Write the contained [`Path`]s to an SVG at `file_path`. Text will NOT be written!
+    pub fn write<P: AsRef<std::path::Path>>(
+        self,
+        file_path: P,
+    ) -> Result<(), LyonTranslationError> {
+        to_file(self.to_svg_string()?.as_bytes(), file_path)
+    }
+
+    /// Write the contained [`Path`]s to a gzip-compressed SVG (`.svgz`) at `file_path`.
+    /// Text will NOT be written!
+    #[cfg(all(feature = "flate2", not(target_arch = "wasm32")))]
+    pub fn write_svgz<P: AsRef<std::path::Path>>(
+        self,
+        file_path: P,
+    ) -> Result<(), LyonTranslationError> {
+        to_svgz_file(&self.to_svg_string()?, file_path)
+    }
+
+    /// Render the contained [`Path`]s to a PNG at `file_path`, with `scale` pixels per
+    /// unit of the SVG size. Text will NOT be written!
+    #[cfg(feature = "raster")]
+    pub fn write_png<P: AsRef<std::path::Path>>(
+        self,
+        file_path: P,
+        scale: f32,
+    ) -> Result<(), LyonTranslationError> {
+        let document = self.prepare()?;
+        to_file(&raster::render_png(&document.tree, scale)?, file_path)
+    }
+
+    /// Draw the contained [`Path`]s onto `canvas` with `scale` pixels per unit of the SVG
+    /// size, as a preview of what [`LyonWriter::write`] produces. The canvas is resized to
+    /// fit the drawing. Text will NOT be drawn!
+    #[cfg(all(feature = "canvas", target_arch = "wasm32"))]
+    pub fn write_to_canvas(
+        self,
+        canvas: &web_sys::HtmlCanvasElement,
+        scale: f32,
+    ) -> Result<(), LyonTranslationError> {
+        let document = self.prepare()?;
+        raster::render_canvas(&document.tree, scale, canvas)
+    }
+
+    /// Write an SVG per [layer](LyonWriter::layer) to the directory `dir`, named after the
+    /// layer (with characters other than alphanumerics, `-` and `_` replaced by `_`).
+    /// Text will NOT be written!
+    ///
+    /// All the files share the size and view box of the whole document, so that they can be
+    /// stacked. Nodes outside of layers are not written. Returns the paths of the files.
+    pub fn write_layers<P: AsRef<std::path::Path>>(
+        self,
+        dir: P,
+    ) -> Result<Vec<std::path::PathBuf>, LyonTranslationError> {
+        write_layer_files(&self.prepare()?, dir.as_ref())
+    }
+
+    /// Serialize the contained [`Path`]s to a percent-encoded `data:image/svg+xml` URI, to
+    /// be used in `<img src>` or CSS `url()`. Text will NOT be written!
+    pub fn to_data_uri(self) -> Result<String, LyonTranslationError> {
+        Ok(data_uri(&self.to_svg_string()?))
+    }
+
+    /// Serialize the contained [`Path`]s to an `image/svg+xml` [`web_sys::Blob`], to be
+    /// handed over to JS instead of downloading it with [`LyonWriter::write`]. Text will
+    /// NOT be written!
+    ///
+    /// It does not access the DOM, so it can be called from web workers.
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    pub fn to_blob(self) -> Result<web_sys::Blob, LyonTranslationError> {
+        to_blob(self.to_svg_string()?.as_bytes(), "image/svg+xml")
+    }
+
+    /// Serialize the contained [`Path`]s to the UTF-8 bytes of the SVG in a
+    /// [`js_sys::Uint8Array`], e.g. to be transferred from a web worker to the main thread.
+    /// Text will NOT be written!
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    pub fn to_uint8_array(self) -> Result<js_sys::Uint8Array, LyonTranslationError> {
+        Ok(js_sys::Uint8Array::from(self.to_svg_string()?.as_bytes()))
+    }
+
+    /// Show the contained [`Path`]s in the page, replacing the content of the DOM element
+    /// with id `element_id` by the SVG. Text will NOT be written!
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    pub fn write_to_element(self, element_id: &str) -> Result<(), LyonTranslationError> {
+        to_element(&self.to_svg_string()?, element_id)
+    }
+
+    /// Write the contained [`Path`]s to a file chosen in the save dialog of the browser,
+    /// which suggests `file_name`. Unlike [`LyonWriter::write`], existing files can be
+    /// overwritten. Text will NOT be written!
+    ///
+    /// Browsers without the [File System Access API](https://developer.mozilla.org/en-US/docs/Web/API/Window/showSaveFilePicker)
+    /// download the file as in [`LyonWriter::write`].
+    #[cfg(all(feature = "save-picker", target_arch = "wasm32"))]
+    pub async fn write_with_picker(self, file_name: &str) -> Result<(), LyonTranslationError> {
+        to_picked_file(self.to_svg_string()?.as_bytes(), file_name).await
+    }
+
+    /// Convert the contained [`Path`]s to a vector PDF at `file_path`. Text will NOT be
+    /// written!
+    #[cfg(feature = "svg2pdf")]
+    pub fn write_pdf<P: AsRef<std::path::Path>>(
+        self,
+        file_path: P,
+    ) -> Result<(), LyonTranslationError> {
+        to_file(&pdf::render_pdf(&self.to_svg_string()?)?, file_path)
+    }
+
+    /// Write the contained [`Path`]s as an SVG to `writer` (a file, a socket, a buffer...).
+    /// Text will NOT be written!
+    ///
+    /// Only the path data of the nodes, which makes up most of large documents, is streamed
+    /// to `writer` instead of being formatted into the SVG string first; the rest of the
+    /// markup is still serialized in memory. Prefer it to [`LyonWriter::write`] for large
+    /// documents, wrapping files in a [`BufWriter`](std::io::BufWriter).
+    pub fn write_to<W: std::io::Write>(self, writer: W) -> Result<(), LyonTranslationError> {
+        self.prepare()?
+            .write_to(writer)
+            .map_err(|e| LyonTranslationError::IoWrite(Box::new(e)))
+    }
+
+    /// Serialize the contained [`Path`]s to an SVG string, without writing any file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(Point2D::new(3.0, 2.0));
+    /// path_builder.line_to(Point2D::new(0.0, 2.0));
+    /// path_builder.end(true);
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push(&path_builder.build(), Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Path should be translatable!");
+    /// let svg = writer.to_svg_string().expect("Path should be serializable!");
+    /// assert!(svg.starts_with("<svg"));
+    /// ```
+    pub fn to_svg_string(self) -> Result<String, LyonTranslationError> {
+        Ok(self.prepare()?.to_svg_string())
+    }
+
+    /// Loads fonts from a font file, building a [`FontProvider`] and enabling writing text.
+    pub fn add_fonts_source(
+        self,
+        font_source: &[u8],
+    ) -> LyonWriter<Option<usvg::fontdb::Database>> {
+        let mut fonts = usvg::fontdb::Database::new();
+        fonts.load_font_data(font_source.to_vec());
+        self.with_fontdb(Some(fonts))
+    }
+}
+
+impl Default for LyonWriter<NoText> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Styled path, as accepted by [`push`](LyonWriter::push).
+pub type StyledPath = (Path, Option<Fill>, Option<Stroke>, Option<SvgTransform>);
+
+/// Bulk insertion of styled paths.
+///
+/// Since `extend` cannot fail, paths that cannot be translated (e.g., empty paths) are skipped.
+impl<T> Extend<StyledPath> for LyonWriter<T> {
+    fn extend<I: IntoIterator<Item = StyledPath>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.nodes.reserve(iter.size_hint().0);
+        let tracked = !self.in_artboard();
+        if let Some(lazy) = self.lazy.as_mut() {
+            for (path, fill, stroke, transform) in iter {
+                let placeholder = usvg::Node::new(NodeKind::Group(Group::default()));
+                let props = PathProps {
+                    fill,
+                    stroke,
+                    transform,
+                    ..Default::default()
+                };
+                lazy.paths.push(path);
+                lazy.entries.push(LazyEntry {
+                    placeholder: placeholder.clone(),
+                    props,
+                    droppable: true,
+                    tracked,
+                });
+                self.nodes.push(placeholder);
+            }
+            return;
+        }
+        for (path, fill, stroke, transform) in iter {
+            if let Some(data) = translate_with_tolerance(path.as_slice(), self.tolerance) {
+                let data = self.share_data(data);
+                self.add(usvg::Node::new(NodeKind::Path(svg_path_with_attributes(
+                    data, fill, stroke, transform,
+                ))));
+            }
+        }
+    }
+}
+
+/// Collect styled paths into a writer.
+///
+/// Paths that cannot be translated (e.g., empty paths) are skipped.
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{Color, LyonWriter, fill};
+/// use lyon_path::Path;
+/// use lyon_path::geom::euclid::Point2D;
+///
+/// let writer: LyonWriter<_> = (1..10)
+///     .map(|i| {
+///         let mut path_builder = Path::builder();
+///         path_builder.begin(Point2D::new(i as f32, 0.0));
+///         path_builder.line_to(Point2D::new(i as f32, 1.0));
+///         path_builder.line_to(Point2D::new(i as f32 + 1.0, 0.0));
+///         path_builder.end(true);
+///         (path_builder.build(), Some(fill(Color::black(), 1.0)), None, None)
+///     })
+///     .collect();
+/// ```
+impl FromIterator<StyledPath> for LyonWriter<NoText> {
+    fn from_iter<I: IntoIterator<Item = StyledPath>>(iter: I) -> Self {
+        let mut writer = LyonWriter::new();
+        writer.extend(iter);
+        writer
+    }
+}
+
+/// Marker trait that changes the behavior of `write` for [`LyonWriter`]
+/// and allows for writing text to the SVG.
+pub trait FontProvider {
+    fn get_fontdb(self) -> usvg::fontdb::Database;
+}
+impl FontProvider for usvg::fontdb::Database {
+    fn get_fontdb(self) -> usvg::fontdb::Database {
+        self
+    }
+}
+
+/// Implemented for `Option<T>` to be able to ergonomically take it without cloning.
+impl<T: FontProvider> LyonWriter<Option<T>> {
+    /// Add [`Text`](usvg::Text) to the writer, filling it as an unique [`TextChunk`](usvg::TextChunk) whose
+    /// [`TextSpan`](usvg::TextSpan) style applies to all the text. Line breaks (`\n`) start
+    /// a new line (see [`RichText`] for more options).
+    ///
+    /// Requires having called [`LyonWriter::add_fonts`] beforehand.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, SvgTransform, fill, stroke};
+    /// use lyon_path::Path;
+    /// use lyon_path::geom::euclid::Point2D;
+    ///
+    /// let file_path = "text.svg";
+    ///
+    /// let writer = LyonWriter::new();
+    /// let mut fontdb = usvg::fontdb::Database::new();
+    /// fontdb.load_system_fonts();
+    /// let mut writer = writer.add_fonts(fontdb);
+    /// let mut path_builder = Path::builder();
+    /// path_builder.begin(Point2D::origin());
+    /// path_builder.line_to(
+    ///     Point2D::new(3.0, 2.0),
+    /// );
+    /// path_builder.end(true);
+    /// writer
+    ///     .push(
+    ///         &path_builder.build(),
+    ///         None,
+    ///         Some(stroke(Color::black(), 1.0, 1.0)),
+    ///         Some(SvgTransform::from_translate(2.0, 2.0)),
+    ///     )
+    ///     .expect("Path 1 should be writable!");
+    ///
+    /// // push the created path with some fill and stroke, in the origin
+    /// writer
+    ///     .push_text(
+    ///         "hello".to_string(),
+    ///         vec!["Arial".to_string()],
+    ///         12.0,
+    ///         SvgTransform::from_translate(0., 0.),
+    ///         Some(fill(usvg::Color::black(), 1.0)),
+    ///         Some(stroke(usvg::Color::black(), 1.0, 1.0)),
+    ///     )
+    ///     .expect("Text should be writable!");
+    /// let mut path_builder = Path::builder();
+    /// // finally, write the SVG, Text with be converted to SvgPath
+    /// writer.write(file_path).expect("Writing should not panic!");
+    ///
+    /// # std::fs::remove_file(&file_path).unwrap();
+    /// ```
+    pub fn push_text(
+        &mut self,
+        text: String,
+        font_families: Vec<String>,
+        font_size: f32,
+        transform: SvgTransform,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let node = usvg::Node::new(create_text_node(
+            text,
+            transform,
+            fill,
+            stroke,
+            font_families,
+            font_size,
+        )?);
+        self.add(node);
+        Ok(self)
+    }
+
+    /// Loads fonts from a font file, building a [`FontProvider`] if needed and enabling writing text.
+    pub fn add_fonts_source(
+        mut self,
+        font_source: &[u8],
+    ) -> LyonWriter<Option<usvg::fontdb::Database>> {
+        let mut fonts = self
+            .fontdb
+            .take()
+            .map(|f| f.get_fontdb())
+            .unwrap_or_default();
+        fonts.load_font_data(font_source.to_vec());
+        self.with_fontdb(Some(fonts))
+    }
+
+    /// Write the contained [`Path`]s to an SVG at `file_path`, converting all [`Text`](usvg::Text) nodes
+    /// to paths.
+    pub fn write<P: AsRef<std::path::Path>>(
+        self,
+        file_path: P,
+    ) -> Result<(), LyonTranslationError> {
+        to_file(self.to_svg_string()?.as_bytes(), file_path)
+    }
+
+    /// Write the contained [`Path`]s to a gzip-compressed SVG (`.svgz`) at `file_path`,
+    /// converting all [`Text`](usvg::Text) nodes to paths.
+    #[cfg(all(feature = "flate2", not(target_arch = "wasm32")))]
+    pub fn write_svgz<P: AsRef<std::path::Path>>(
+        self,
+        file_path: P,
+    ) -> Result<(), LyonTranslationError> {
+        to_svgz_file(&self.to_svg_string()?, file_path)
+    }
+
+    /// Render the contained [`Path`]s to a PNG at `file_path`, with `scale` pixels per
+    /// unit of the SVG size, converting all [`Text`](usvg::Text) nodes to paths.
+    #[cfg(feature = "raster")]
+    pub fn write_png<P: AsRef<std::path::Path>>(
+        mut self,
+        file_path: P,
+        scale: f32,
+    ) -> Result<(), LyonTranslationError> {
+        let fontdb = self
+            .fontdb
+            .take()
+            .ok_or(LyonTranslationError::NoFonts)?
+            .get_fontdb();
+        let mut document = self.prepare_with_fonts(Some(&fontdb))?;
+        document.tree.convert_text(&fontdb);
+        to_file(&raster::render_png(&document.tree, scale)?, file_path)
+    }
+
+    /// Draw the contained [`Path`]s onto `canvas`, as in the [`LyonWriter<NoText>`] version,
+    /// converting all [`Text`](usvg::Text) nodes to paths.
+    #[cfg(all(feature = "canvas", target_arch = "wasm32"))]
+    pub fn write_to_canvas(
+        mut self,
+        canvas: &web_sys::HtmlCanvasElement,
+        scale: f32,
+    ) -> Result<(), LyonTranslationError> {
+        let fontdb = self
+            .fontdb
+            .take()
+            .ok_or(LyonTranslationError::NoFonts)?
+            .get_fontdb();
+        let mut document = self.prepare_with_fonts(Some(&fontdb))?;
+        document.tree.convert_text(&fontdb);
+        raster::render_canvas(&document.tree, scale, canvas)
+    }
+
+    /// Write an SVG per [layer](LyonWriter::layer) to the directory `dir`, as in the
+    /// [`LyonWriter<NoText>`] version, converting all [`Text`](usvg::Text) nodes to paths.
+    pub fn write_layers<P: AsRef<std::path::Path>>(
+        mut self,
+        dir: P,
+    ) -> Result<Vec<std::path::PathBuf>, LyonTranslationError> {
+        let fontdb = self
+            .fontdb
+            .take()
+            .ok_or(LyonTranslationError::NoFonts)?
+            .get_fontdb();
+        let mut document = self.prepare_with_fonts(Some(&fontdb))?;
+        document.tree.convert_text(&fontdb);
+        write_layer_files(&document, dir.as_ref())
+    }
+
+    /// Serialize the contained [`Path`]s to a percent-encoded `data:image/svg+xml` URI, to
+    /// be used in `<img src>` or CSS `url()`, converting all [`Text`](usvg::Text) nodes to
+    /// paths.
+    pub fn to_data_uri(self) -> Result<String, LyonTranslationError> {
+        Ok(data_uri(&self.to_svg_string()?))
+    }
+
+    /// Serialize the contained [`Path`]s to an `image/svg+xml` [`web_sys::Blob`], as in the
+    /// [`LyonWriter<NoText>`] version, converting all [`Text`](usvg::Text) nodes to paths.
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    pub fn to_blob(self) -> Result<web_sys::Blob, LyonTranslationError> {
+        to_blob(self.to_svg_string()?.as_bytes(), "image/svg+xml")
+    }
+
+    /// Serialize the contained [`Path`]s to the UTF-8 bytes of the SVG in a
+    /// [`js_sys::Uint8Array`], as in the [`LyonWriter<NoText>`] version, converting all
+    /// [`Text`](usvg::Text) nodes to paths.
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    pub fn to_uint8_array(self) -> Result<js_sys::Uint8Array, LyonTranslationError> {
+        Ok(js_sys::Uint8Array::from(self.to_svg_string()?.as_bytes()))
+    }
+
+    /// Show the contained [`Path`]s in the page, as in the [`LyonWriter<NoText>`] version,
+    /// converting all [`Text`](usvg::Text) nodes to paths.
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    pub fn write_to_element(self, element_id: &str) -> Result<(), LyonTranslationError> {
+        to_element(&self.to_svg_string()?, element_id)
+    }
+
+    /// Write the contained [`Path`]s to a file chosen in the save dialog of the browser, as
+    /// in the [`LyonWriter<NoText>`] version, converting all [`Text`](usvg::Text) nodes to
+    /// paths.
+    #[cfg(all(feature = "save-picker", target_arch = "wasm32"))]
+    pub async fn write_with_picker(self, file_name: &str) -> Result<(), LyonTranslationError> {
+        to_picked_file(self.to_svg_string()?.as_bytes(), file_name).await
+    }
+
+    /// Convert the contained [`Path`]s to a vector PDF at `file_path`, converting all
+    /// [`Text`](usvg::Text) nodes to paths.
+    #[cfg(feature = "svg2pdf")]
+    pub fn write_pdf<P: AsRef<std::path::Path>>(
+        self,
+        file_path: P,
+    ) -> Result<(), LyonTranslationError> {
+        to_file(&pdf::render_pdf(&self.to_svg_string()?)?, file_path)
+    }
+
+    /// Write the contained [`Path`]s as an SVG to `writer` (a file, a socket, a buffer...),
+    /// converting all [`Text`](usvg::Text) nodes to paths.
+    ///
+    /// As in the [`LyonWriter<NoText>`] version, only the path data of the nodes is streamed
+    /// to `writer`.
+    pub fn write_to<W: std::io::Write>(mut self, writer: W) -> Result<(), LyonTranslationError> {
+        let fontdb = self
+            .fontdb
+            .take()
+            .ok_or(LyonTranslationError::NoFonts)?
+            .get_fontdb();
+        let mut document = self.prepare_with_fonts(Some(&fontdb))?;
+        document.tree.convert_text(&fontdb);
+        document
+            .write_to(writer)
+            .map_err(|e| LyonTranslationError::IoWrite(Box::new(e)))
+    }
+
+    /// Serialize the contained [`Path`]s to an SVG string, converting all
+    /// [`Text`](usvg::Text) nodes to paths, without writing any file.
+    pub fn to_svg_string(mut self) -> Result<String, LyonTranslationError> {
+        let fontdb = self
+            .fontdb
+            .take()
+            .ok_or(LyonTranslationError::NoFonts)?
+            .get_fontdb();
+        let mut document = self.prepare_with_fonts(Some(&fontdb))?;
+        document.tree.convert_text(&fontdb);
+        Ok(document.to_svg_string())
+    }
+
+    /// Write the contained [`Path`]s to an SVG at `file_path`, keeping the text as editable
+    /// `<text>` elements instead of converting it to paths.
+    ///
+    /// The output is lighter, but it will only render as expected where the fonts are
+    /// available. Fonts are not needed to write it.
+    pub fn write_with_text<P: AsRef<std::path::Path>>(
+        mut self,
+        file_path: P,
+    ) -> Result<(), LyonTranslationError> {
+        let fonts = self.fontdb.take().map(FontProvider::get_fontdb);
+        let mut document = self.prepare_with_fonts(fonts.as_ref())?;
+        document.keep_text();
+        to_file(document.to_svg_string().as_bytes(), file_path)?;
+        Ok(())
+    }
+}
+
+fn lyon_path_to_svg_with_attributes(
+    path: &Path,
+    fill: Option<Fill>,
+    stroke: Option<Stroke>,
+    transform: Option<SvgTransform>,
+) -> Option<SvgPath> {
+    Some(svg_path_with_attributes(
+        Rc::new(lyon_path_to_usvg(path.as_slice())?),
+        fill,
+        stroke,
+        transform,
+    ))
+}
+
+fn svg_path_with_attributes(
+    data: Rc<PathData>,
+    fill: Option<Fill>,
+    stroke: Option<Stroke>,
+    transform: Option<SvgTransform>,
+) -> SvgPath {
+    let mut op = SvgPath::new(data);
+    op.fill = fill;
+    op.stroke = stroke;
+    if let Some(trans) = transform {
+        op.transform = trans;
+    }
+    op
+}
+
+/// Translate `path`, quantized to `tolerance` if given (see [`LyonWriter::with_tolerance`]).
+fn translate_with_tolerance(path: PathSlice, tolerance: Option<f32>) -> Option<PathData> {
+    apply_tolerance(lyon_path_to_usvg(path)?, tolerance)
+}
+
+/// Quantize `data` to `tolerance`, if given.
+fn apply_tolerance(data: PathData, tolerance: Option<f32>) -> Option<PathData> {
+    match tolerance {
+        Some(tolerance) => simplify::quantize(&data, tolerance),
+        None => Some(data),
+    }
+}
+
+/// Check that all the points of `path`, pushed as the `node`-th node, are finite.
+pub(crate) fn check_finite(path: PathSlice, node: usize) -> Result<(), LyonTranslationError> {
+    match path.iter().find_map(|event| non_finite_point(&event)) {
+        Some(point) => Err(LyonTranslationError::NonFiniteCoordinate {
+            node,
+            x: point.x,
+            y: point.y,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// First point of `event` that is NaN or infinite, if any.
+pub(crate) fn non_finite_point(event: &PathEvent) -> Option<lyon_path::math::Point> {
+    let points = match *event {
+        Event::Begin { at } => [at, at, at],
+        Event::Line { to, .. } => [to, to, to],
+        Event::Quadratic { ctrl, to, .. } => [ctrl, to, to],
+        Event::Cubic {
+            ctrl1, ctrl2, to, ..
+        } => [ctrl1, ctrl2, to],
+        Event::End { .. } => return None,
+    };
+    points
+        .into_iter()
+        .find(|p| !(p.x.is_finite() && p.y.is_finite()))
+}
+
+fn lyon_path_to_usvg(path: PathSlice) -> Option<PathData> {
+    // a first pass to allocate the data at once, instead of growing it segment by segment
+    let (verbs, points) = path
+        .iter()
+        .fold((0, 0), |(verbs, points), event| match event {
+            Event::Begin { .. } | Event::Line { .. } => (verbs + 1, points + 1),
+            Event::Quadratic { .. } => (verbs + 1, points + 2),
+            Event::Cubic { .. } => (verbs + 1, points + 3),
+            Event::End { close: true, .. } => (verbs + 1, points),
+            Event::End { close: false, .. } => (verbs, points),
+        });
+    events_to_usvg(path.iter(), PathBuilder::with_capacity(verbs, points))
+}
+
+/// Translate a stream of path events with `upath_builder`.
+fn events_to_usvg(
+    events: impl IntoIterator<Item = PathEvent>,
+    upath_builder: PathBuilder,
+) -> Option<PathData> {
+    let mut sink = EventSink::new(upath_builder);
+    for event in events {
+        sink.event(event);
+    }
+    sink.finish()
+}
+
+/// Incremental translation of [`PathEvent`]s to path data.
+pub(crate) struct EventSink {
+    builder: PathBuilder,
+    // end of the open subpath, if any
+    current: Option<Point>,
+}
+
+impl EventSink {
+    pub(crate) fn new(builder: PathBuilder) -> Self {
+        Self {
+            builder,
+            current: None,
+        }
+    }
+
+    pub(crate) fn event(&mut self, event: PathEvent) {
+        // segments that do not continue the open subpath start a new one, so that subpaths
+        // without segments are not written
+        if let Event::Line { from, .. }
+        | Event::Quadratic { from, .. }
+        | Event::Cubic { from, .. } = event
+        {
+            if self.current != Some(from) {
+                self.builder.move_to(from.x, from.y);
+            }
+        }
+        match event {
+            Event::Begin { .. } => self.current = None,
+            Event::Line { to, .. } => {
+                self.builder.line_to(to.x, to.y);
+                self.current = Some(to);
+            }
+            Event::Quadratic { ctrl, to, .. } => {
+                self.builder.quad_to(ctrl.x, ctrl.y, to.x, to.y);
+                self.current = Some(to);
+            }
+            Event::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => {
+                self.builder
+                    .cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y);
+                self.current = Some(to);
+            }
+            Event::End { close, .. } => {
+                // the close command draws the closing segment by itself
+                if close && self.current.is_some() {
+                    self.builder.close();
+                }
+                self.current = None;
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> Option<PathData> {
+        self.builder.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lyon_path::geom::euclid::Point2D;
+
+    use super::*;
+
+    #[test]
+    fn lines_deserialize() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+        assert!(lyon_path_to_usvg(path.as_slice()).unwrap().len() == 4);
+    }
+    #[test]
+    fn subpaths_are_kept_apart() {
+        // a donut: the hole winds the other way
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 0.0));
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.line_to(Point2D::new(0.0, 4.0));
+        path_builder.end(true);
+        path_builder.begin(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(1.0, 3.0));
+        path_builder.line_to(Point2D::new(3.0, 3.0));
+        path_builder.line_to(Point2D::new(3.0, 1.0));
+        path_builder.end(true);
+        assert_eq!(
+            path_to_d(&path_builder.build()),
+            "M 0 0 L 4 0 L 4 4 L 0 4 Z M 1 1 L 1 3 L 3 3 L 3 1 Z"
+        );
+        // disjoint open subpaths, one of them starting where the other ends
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.quadratic_bezier_to(Point2D::new(1.0, 1.0), Point2D::new(2.0, 0.0));
+        path_builder.end(false);
+        path_builder.begin(Point2D::new(2.0, 0.0));
+        path_builder.line_to(Point2D::new(5.0, 5.0));
+        path_builder.end(false);
+        path_builder.begin(Point2D::new(7.0, 7.0));
+        path_builder.end(false);
+        assert_eq!(
+            path_to_d(&path_builder.build()),
+            "M 0 0 Q 1 1 2 0 M 2 0 L 5 5"
+        );
+    }
+
+    #[test]
+    fn attributes_are_ok() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.quadratic_bezier_to(Point2D::new(2.0, 1.0), Point2D::new(3.0, 2.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+        assert!(
+            lyon_path_to_svg_with_attributes(&path, None, None, None)
+                .unwrap()
+                .data
+                .len()
+                == 4
+        );
+    }
+    #[test]
+    fn writing_does_not_panic() {
+        let file_path = "tmpthis.svg";
+        let mut writer = LyonWriter::new();
+
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.quadratic_bezier_to(Point2D::new(2.0, 1.0), Point2D::new(3.0, 2.0));
+        path_builder.cubic_bezier_to(
+            Point2D::new(2.0, 1.0),
+            Point2D::new(5.0, 1.0),
+            Point2D::new(3.0, 2.0),
+        );
+        path_builder.end(true);
+        let path = path_builder.build();
+        writer
+            .push(
+                &path,
+                None,
+                Some(stroke(Color::new_rgb(253, 77, 44), 0.8, 2.0)),
+                Some(SvgTransform::from_translate(0.0, 0.0)),
+            )
+            .expect("Path 1 should be writable!");
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.cubic_bezier_to(
+            Point2D::new(2.0, 1.0),
+            Point2D::new(5.0, 1.0),
+            Point2D::new(3.0, 2.0),
+        );
+        path_builder.end(true);
+        let path = path_builder.build();
+        writer
+            .push(
+                &path,
+                None,
+                Some(stroke(Color::black(), 1.0, 1.0)),
+                Some(SvgTransform::from_translate(2.0, 2.0)),
+            )
+            .expect("Path 2 should be writable!");
+        writer.write(file_path).expect("Writing should not panic!");
+
+        std::fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn path_and_texts_do_not_panic() {
+        let file_path = "textex.svg";
+        let mut writer = LyonWriter::new();
+        // push the created path with some fill and stroke, in the origin
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.cubic_bezier_to(
+            Point2D::new(2.0, 1.0),
+            Point2D::new(5.0, 1.0),
+            Point2D::new(3.0, 2.0),
+        );
+        path_builder.end(true);
+        let path = path_builder.build();
+        writer
+            .push(
+                &path,
+                None,
+                Some(stroke(Color::black(), 1.0, 1.0)),
+                Some(SvgTransform::from_translate(2.0, 2.0)),
+            )
+            .expect("Path 1 should be writable!");
+        let mut fontdb = usvg::fontdb::Database::new();
+        fontdb.load_system_fonts();
+        let mut writer = writer.add_fonts(fontdb);
+        writer
+            .push_text(
+                "hello".to_string(),
+                vec!["Arial".to_string()],
+                12.0,
+                SvgTransform::from_translate(0., 0.),
+                Some(fill(usvg::Color::black(), 1.0)),
+                Some(stroke(usvg::Color::black(), 1.0, 1.0)),
+            )
+            .expect("Text should be writable!");
+        // finally, write the SVG, Text with be converted to SvgPath
+        writer.write(file_path).expect("Writing should not panic!");
+        std::fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn groups_keep_their_children() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+
+        let mut sub_writer = LyonWriter::new();
+        sub_writer
+            .push(&path, Some(fill(Color::black(), 1.0)), None, None)
+            .expect("Path should be writable!");
+        let mut writer = LyonWriter::new();
+        writer
+            .push_path_group(
+                [&path, &path],
+                None,
+                Some(stroke(Color::black(), 1.0, 1.0)),
+                SvgTransform::from_translate(2.0, 2.0),
+                Some("paths".to_string()),
+            )
+            .expect("Path group should be writable!");
+        writer
+            .push_writer(sub_writer, SvgTransform::default(), None)
+            .expect("Sub-writer should be writable!");
+        let tree = writer.prepare().expect("Tree should be built!").tree;
+        let group = tree.root.first_child().unwrap();
+        assert_eq!(group.children().count(), 2);
+        let paths = group
+            .children()
+            .find(|node| &*node.id() == "paths")
+            .expect("Group id should be kept!");
+        assert_eq!(paths.children().count(), 2);
+    }
+
+    #[test]
+    fn sub_writers_do_not_share_generated_ids() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.line_to(Point2D::new(4.0, 0.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+
+        let mut sub_writer = LyonWriter::new();
+        let clip = sub_writer.define_clip_rect((2.0, 0.0, 2.0, 4.0)).unwrap();
+        let gradient = Fill::from_paint(sub_writer.linear_gradient(
+            [(0.0, Color::black()), (1.0, Color::white())],
+            Point2D::new(0.0, 0.0),
+            Point2D::new(4.0, 0.0),
+        ));
+        sub_writer
+            .push_with(
+                &path,
+                PathProps::new().fill(gradient.clone()).clip(clip.clone()),
+            )
+            .unwrap()
+            .push_with(&path, PathProps::new().fill(gradient).clip(clip))
+            .unwrap();
+        let mut writer = LyonWriter::new();
+        let clip = writer.define_clip_rect((0.0, 0.0, 2.0, 4.0)).unwrap();
+        writer
+            .push_with(
+                &path,
+                PathProps::new().fill(fill(Color::black(), 1.0)).clip(clip),
+            )
+            .unwrap()
+            .push_writer(sub_writer, SvgTransform::default(), None)
+            .unwrap();
+        let clip = writer.define_clip_rect((0.0, 0.0, 1.0, 1.0)).unwrap();
+        writer
+            .push_with(&path, PathProps::new().clip(clip))
+            .unwrap();
+        let svg = writer.to_svg_string().unwrap();
+        for id in ["roarsvg-clip1", "roarsvg-clip2", "roarsvg-clip4"] {
+            assert_eq!(svg.matches(&format!("<clipPath id=\"{id}\"")).count(), 1);
+        }
+        assert_eq!(svg.matches("<clipPath").count(), 3);
+        assert_eq!(svg.matches("<linearGradient").count(), 1);
+        assert_eq!(svg.matches("url(#roarsvg-linear-gradient3)").count(), 2);
+        assert_eq!(svg.matches("clip-path=\"url(#roarsvg-clip2)\"").count(), 2);
+    }
+
+    #[test]
+    fn sub_writers_keep_their_markup() {
+        use usvg::TreeParsing;
+
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let build_sub_writer = |id: &str| {
+            let mut sub_writer = LyonWriter::new();
+            sub_writer
+                .push_with(&path, PathProps::new().id(id).class("x"))
+                .unwrap()
+                .push_with(&path, PathProps::new().class("hl").title("tip"))
+                .unwrap();
+            sub_writer
+        };
+        let mut writer = LyonWriter::new();
+        writer
+            .push_with(&path, PathProps::new().id("a").class("y"))
+            .unwrap()
+            .push_with(&path, PathProps::new().class("z"))
+            .unwrap();
+        assert!(matches!(
+            writer.push_writer(build_sub_writer("a"), SvgTransform::default(), None),
+            Err(LyonTranslationError::DuplicateId(_))
+        ));
+        writer
+            .push_writer(build_sub_writer("b"), SvgTransform::default(), None)
+            .unwrap();
+        assert!(matches!(
+            writer.push_with(&path, PathProps::new().id("b")),
+            Err(LyonTranslationError::DuplicateId(_))
+        ));
+        let svg = writer.to_svg_string().unwrap();
+        assert!(svg.contains("<path id=\"b\""));
+        assert_eq!(svg.matches("class=\"hl\"").count(), 1);
+        assert_eq!(svg.matches("class=\"z\"").count(), 1);
+        assert!(svg.contains("<title>tip</title>"));
+        assert!(!svg.contains("roarsvg-node"));
+        usvg::Tree::from_str(&svg, &usvg::Options::default()).unwrap();
+    }
+
+    #[test]
+    fn layers_are_inkscape_groups() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+
+        let mut writer = LyonWriter::new();
+        writer.layer("shapes");
+        writer
+            .push(&path, Some(fill(Color::black(), 1.0)), None, None)
+            .expect("Path should be writable!");
+        writer.exit_layer();
+        writer
+            .push(&path, None, Some(stroke(Color::black(), 1.0, 1.0)), None)
+            .expect("Path should be writable!");
+        writer.layer("annotations & labels");
+        writer.layer("shapes");
+        writer
+            .push(&path, None, Some(stroke(Color::black(), 1.0, 1.0)), None)
+            .expect("Path should be writable!");
+        let document = writer.prepare().expect("Tree should be built!");
+        let group = document.tree.root.first_child().unwrap();
+        assert_eq!(group.children().count(), 3);
+        let svg = document.to_svg_string();
+        assert!(svg.contains(&format!("xmlns:inkscape=\"{INKSCAPE_NS}\"")));
+        assert!(svg.contains(
+            "<g id=\"roarsvg-layer1\" inkscape:groupmode=\"layer\" inkscape:label=\"shapes\">"
+        ));
+        assert!(svg.contains("inkscape:label=\"annotations &amp; labels\""));
+    }
+
+    #[test]
+    fn scene_nodes_inherit_transforms() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+
+        let mut writer = LyonWriter::new().with_transform(SvgTransform::from_scale(2.0, 2.0));
+        let parent = writer.add_scene_node(None, SvgTransform::from_translate(1.0, 0.0));
+        let child = writer.add_scene_node(Some(parent), SvgTransform::from_translate(0.0, 3.0));
+        writer
+            .push_child(
+                child,
+                &path,
+                None,
+                Some(stroke(Color::black(), 1.0, 1.0)),
+                None,
+            )
+            .expect("Path should be writable!");
+        assert_eq!(
+            writer.world_transform(child),
+            SvgTransform::from_row(2.0, 0.0, 0.0, 2.0, 2.0, 6.0)
+        );
+        let tree = writer.prepare().expect("Tree should be built!").tree;
+        let parent_node = tree.root.first_child().unwrap().first_child().unwrap();
+        assert_eq!(parent_node.descendants().count(), 3);
+    }
+
+    #[test]
+    fn path_props_are_applied() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+
+        let mut writer = LyonWriter::new();
+        writer
+            .push_with(
+                &path,
+                PathProps::new()
+                    .fill(fill(Color::black(), 1.0))
+                    .transform(SvgTransform::from_translate(1.0, 1.0))
+                    .id("plain"),
+            )
+            .expect("Path should be writable!");
+        writer
+            .push_with(&path, PathProps::new().opacity(0.5).id("faded"))
+            .expect("Path should be writable!");
+        let svg = writer
+            .prepare()
+            .expect("Tree should be built!")
+            .to_svg_string();
+        assert!(svg.contains("<path id=\"plain\" fill=\"#000000\""));
+        assert!(svg.contains("<g id=\"faded\" opacity=\"0.5\">"));
+    }
+
+    #[test]
+    fn pushes_can_be_chained() -> Result<(), LyonTranslationError> {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+
+        let mut writer = LyonWriter::new();
+        writer
+            .push(&path, Some(fill(Color::black(), 1.0)), None, None)?
+            .push_with(&path, PathProps::new().id("second"))?
+            .push_group(Vec::new(), SvgTransform::default())?
+            .push_node(NodeKind::Group(Group::default()));
+        assert_eq!(writer.nodes.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn push_many_is_all_or_nothing() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let paths = vec![path.clone(); 3];
+
+        let mut writer = LyonWriter::new();
+        writer
+            .push_many(&paths, None, Some(stroke(Color::black(), 1.0, 1.0)), None)
+            .expect("Paths should be writable!");
+        assert_eq!(writer.nodes.len(), 3);
+        assert!(writer
+            .push_many([&path, &Path::new()], None, None, None)
+            .is_err());
+        assert_eq!(writer.nodes.len(), 3);
+    }
+
+    #[test]
+    fn size_and_view_box_can_be_set() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+
+        let mut writer = LyonWriter::new().with_view_box(-10.0, -5.0, 20.0, 10.0);
+        writer
+            .push(&path, None, Some(stroke(Color::black(), 1.0, 1.0)), None)
+            .expect("Path should be writable!");
+        let tree = writer.prepare().expect("Tree should be built!").tree;
+        assert_eq!(
+            tree.view_box.rect,
+            NonZeroRect::from_xywh(-10.0, -5.0, 20.0, 10.0).unwrap()
+        );
+        assert_eq!(tree.size, Size::from_wh(20.0, 10.0).unwrap());
+
+        let writer = LyonWriter::new()
+            .with_view_box(0.0, 0.0, 20.0, 10.0)
+            .with_size(200.0, 100.0);
+        let tree = writer.prepare().expect("Tree should be built!").tree;
+        assert_eq!(tree.size, Size::from_wh(200.0, 100.0).unwrap());
+        assert!(LyonWriter::new()
+            .with_view_box(0.0, 0.0, 1.0, 1.0)
+            .with_size(0.0, 1.0)
+            .prepare()
+            .is_err());
+    }
+
+    #[test]
+    fn gradients_are_written_to_defs() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+
+        let mut writer = LyonWriter::new();
+        let paint = writer.linear_gradient(
+            [(0.0, Color::black()), (1.0, Color::white())],
+            Point2D::new(0.0, 0.0),
+            Point2D::new(2.0, 0.0),
+        );
+        writer
+            .push(&path, Some(Fill::from_paint(paint.clone())), None, None)
+            .expect("Path should be writable!")
+            .push(
+                &path,
+                None,
+                Some(Stroke {
+                    paint,
+                    ..Default::default()
+                }),
+                None,
+            )
+            .expect("Path should be writable!");
+        let svg = writer
+            .prepare()
+            .expect("Tree should be built!")
+            .to_svg_string();
+        assert_eq!(svg.matches("<linearGradient").count(), 1);
+        assert!(svg.contains("fill=\"url(#roarsvg-linear-gradient1)\""));
+        assert!(svg.contains("stroke=\"url(#roarsvg-linear-gradient1)\""));
+    }
+
+    #[test]
+    fn radial_gradients_need_a_valid_radius() {
+        let mut writer = LyonWriter::new();
+        let stops = [(0.0, Color::white()), (1.0, Color::black())];
+        assert!(writer
+            .radial_gradient(Point2D::new(1.0, 1.0), -1.0, None, stops)
+            .is_err());
+        let paint = writer
+            .radial_gradient(Point2D::new(1.0, 1.0), 2.0, None, stops)
+            .expect("Gradient should be valid!");
+        let Paint::RadialGradient(gradient) = paint else {
+            panic!("Paint should be a radial gradient!")
+        };
+        assert_eq!((gradient.fx, gradient.fy), (1.0, 1.0));
+        assert_eq!(gradient.stops.len(), 2);
+    }
+
+    #[test]
+    fn patterns_are_written_to_defs() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+
+        let mut writer = LyonWriter::new();
+        assert!(writer.define_pattern(&path, 0.0, 1.0, None, None).is_err());
+        let pattern = writer
+            .define_pattern(&path, 2.0, 2.0, Some(fill(Color::black(), 1.0)), None)
+            .expect("Pattern should be valid!");
+        writer
+            .push(&path, Some(fill_pattern(&pattern, 1.0)), None, None)
+            .expect("Path should be writable!");
+        let svg = writer
+            .prepare()
+            .expect("Tree should be built!")
+            .to_svg_string();
+        assert_eq!(svg.matches("<pattern").count(), 1);
+        assert!(svg.contains("fill=\"url(#roarsvg-pattern1)\""));
+    }
+
+    #[test]
+    fn dasharrays_are_normalized() {
+        let dashed = stroke_dashed(Color::black(), 1.0, 1.0, vec![2.0, 1.0, 3.0], 0.5);
+        assert_eq!(dashed.dasharray, Some(vec![2.0, 1.0, 3.0, 2.0, 1.0, 3.0]));
+        assert_eq!(dashed.dashoffset, 0.5);
+        assert!(
+            stroke_dashed(Color::black(), 1.0, 1.0, vec![2.0, -1.0], 0.0)
+                .dasharray
+                .is_none()
+        );
+        assert!(stroke_dashed(Color::black(), 1.0, 1.0, vec![], 0.0)
+            .dasharray
+            .is_none());
+    }
+
+    #[test]
+    fn stroke_builder_sets_caps_and_joins() {
+        let built = StrokeBuilder::new(Color::black(), 2.0)
+            .cap(LineCap::Square)
+            .join(LineJoin::Bevel)
+            .miter_limit(0.5)
+            .build();
+        assert_eq!(built.linecap, LineCap::Square);
+        assert_eq!(built.linejoin, LineJoin::Bevel);
+        assert_eq!(built.miterlimit.get(), 1.0);
+        assert_eq!(built.width.get(), 2.0);
+    }
+
+    #[test]
+    fn fill_rule_is_written() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::new(0.0, 0.0));
+        path_builder.line_to(Point2D::new(4.0, 0.0));
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+        let mut writer = LyonWriter::new();
+        writer
+            .push(&path, Some(fill_evenodd(Color::black(), 1.0)), None, None)
+            .unwrap()
+            .push_with(
+                &path,
+                PathProps::new()
+                    .fill(fill(Color::black(), 1.0))
+                    .fill_rule(FillRule::EvenOdd),
+            )
+            .unwrap();
+        let svg = writer.prepare().unwrap().to_svg_string();
+        assert_eq!(svg.matches("fill-rule=\"evenodd\"").count(), 2);
+    }
+
+    #[test]
+    fn paint_order_is_written() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let mut writer = LyonWriter::new();
+        writer
+            .push_with(
+                &path,
+                PathProps::new()
+                    .stroke(stroke(Color::white(), 1.0, 2.0))
+                    .paint_order(PaintOrder::StrokeAndFill),
+            )
+            .unwrap();
+        let svg = writer.prepare().unwrap().to_svg_string();
+        assert!(svg.contains("paint-order=\"stroke\""));
+    }
+
+    #[test]
+    fn text_can_be_kept_as_text() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
+        let mut writer = LyonWriter::new().add_fonts(usvg::fontdb::Database::new());
+        writer
+            .push(
+                &path_builder.build(),
+                None,
+                Some(stroke(Color::black(), 1.0, 1.0)),
+                None,
+            )
+            .unwrap();
+        let paint = writer.linear_gradient(
+            [(0.0, Color::black()), (1.0, Color::white())],
+            Point2D::origin(),
+            Point2D::new(1.0, 0.0),
+        );
+        writer
+            .push_rich_text(
+                RichText::new()
+                    .span(
+                        "a < b",
+                        SpanStyle::new(vec!["DejaVu Sans".to_string()], 12.0)
+                            .fill(Fill::from_paint(paint))
+                            .font(FontDesc::new().weight(700)),
+                    )
+                    .span("\nc", SpanStyle::new(vec!["Arial".to_string()], 10.0)),
+            )
+            .unwrap();
+        let mut document = writer.prepare().unwrap();
+        document.keep_text();
+        let svg = document.to_svg_string();
+        assert!(svg.contains("<linearGradient id=\"roarsvg-linear-gradient1\""));
+        assert!(svg.contains(
+            "<tspan font-family=\"&apos;DejaVu Sans&apos;\" font-size=\"12\" font-weight=\"700\" \
+             fill=\"url(#roarsvg-linear-gradient1)\">a &lt; b</tspan>"
+        ));
+        assert!(svg.contains("<tspan x=\"0\" y=\"14.4"));
+        assert!(svg.contains("</text></g>"));
+    }
+
+    #[test]
+    fn markup_is_written_on_its_elements() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let mut writer = LyonWriter::new().add_fonts(usvg::fontdb::Database::new());
+        writer
+            .group_with(GroupProps::new().class("panel").link("#top"), |g| {
+                g.push_with(&path, PathProps::new().class("edge").id("e1"))?;
+                g.push_with(&path, PathProps::new().class("edge").opacity(0.5))?;
+                Ok(())
+            })
+            .unwrap();
+        writer
+            .push_rich_text(
+                RichText::new()
+                    .span("a", SpanStyle::new(vec!["Arial".to_string()], 10.0))
+                    .id("label")
+                    .class("label")
+                    .title("A & B")
+                    .data("kegg", "C00002 \"ATP\""),
+            )
+            .unwrap();
+        let mut document = writer.prepare().unwrap();
+        document.keep_text();
+        let svg = document.to_svg_string();
+        assert!(svg.contains("<a href=\"#top\" class=\"panel\">"));
+        assert_eq!(svg.matches("</a>").count(), 1);
+        assert!(svg.contains("<path id=\"e1\" fill=\"none\""));
+        assert!(svg.contains("class=\"edge\"/>"));
+        assert!(svg.contains("<g opacity=\"0.5\" class=\"edge\">"));
+        assert!(svg.contains(
+            "<g id=\"label\" class=\"label\" data-kegg=\"C00002 &quot;ATP&quot;\">\
+             <title>A &amp; B</title><text"
+        ));
+        // generated ids are not written
+        assert!(!svg.contains("roarsvg-node"));
     }
-    upath_builder.finish()
-}
 
-#[cfg(test)]
-mod tests {
-    use lyon_path::geom::euclid::Point2D;
+    #[test]
+    fn ids_with_markup_are_not_shared() {
+        use usvg::TreeParsing;
 
-    use super::*;
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let mut writer = LyonWriter::new();
+        writer
+            .push_with(&path, PathProps::new().id("a").class("x"))
+            .unwrap();
+        assert!(matches!(
+            writer.push_with(&path, PathProps::new().id("a").class("y")),
+            Err(LyonTranslationError::DuplicateId(_))
+        ));
+        assert!(matches!(
+            writer.push_with(&path, PathProps::new().id("a")),
+            Err(LyonTranslationError::DuplicateId(_))
+        ));
+        assert!(matches!(
+            writer.push_with(&path, PathProps::new().id("roarsvg-node1")),
+            Err(LyonTranslationError::DuplicateId(_))
+        ));
+        // plain ids can still be shared
+        writer
+            .push_with(&path, PathProps::new().id("b"))
+            .unwrap()
+            .push_with(&path, PathProps::new().id("b"))
+            .unwrap()
+            .push_with(&path, PathProps::new().class("y"))
+            .unwrap();
+        let svg = writer.to_svg_string().unwrap();
+        assert_eq!(svg.matches("class=").count(), 2);
+        usvg::Tree::from_str(&svg, &usvg::Options::default()).unwrap();
+    }
 
     #[test]
-    fn lines_deserialize() {
+    fn group_ids_are_not_shared_with_markup() {
         let mut path_builder = Path::builder();
         path_builder.begin(Point2D::origin());
-        path_builder.line_to(Point2D::new(1.0, 1.0));
-        path_builder.line_to(Point2D::new(2.0, 1.0));
-        path_builder.end(true);
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
         let path = path_builder.build();
-        assert!(lyon_path_to_usvg(&path).unwrap().len() == 5);
+        let mut writer = LyonWriter::new();
+        writer
+            .push_with(&path, PathProps::new().id("a").class("x"))
+            .unwrap();
+        let black = Some(stroke(Color::black(), 1.0, 1.0));
+        for id in ["a", "roarsvg-clip1"] {
+            assert!(matches!(
+                writer.push_path_group(
+                    [&path],
+                    None,
+                    black.clone(),
+                    SvgTransform::default(),
+                    Some(id.to_string())
+                ),
+                Err(LyonTranslationError::DuplicateId(_))
+            ));
+            assert!(matches!(
+                writer.push_writer(
+                    LyonWriter::new(),
+                    SvgTransform::default(),
+                    Some(id.to_string())
+                ),
+                Err(LyonTranslationError::DuplicateId(_))
+            ));
+        }
+        writer
+            .push_path_group([&path], None, black, SvgTransform::default(), None)
+            .unwrap()
+            .push_writer(LyonWriter::new(), SvgTransform::default(), Some("b".into()))
+            .unwrap();
+        assert_eq!(writer.to_svg_string().unwrap().matches("class=").count(), 1);
     }
+
     #[test]
-    fn attributes_are_ok() {
+    fn svg_can_be_written_to_a_buffer() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
+        let mut writer = LyonWriter::new();
+        writer
+            .push(
+                &path_builder.build(),
+                None,
+                Some(stroke(Color::black(), 1.0, 1.0)),
+                None,
+            )
+            .unwrap();
+        let mut buffer = Vec::new();
+        writer.write_to(&mut buffer).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+        assert!(svg.starts_with("<svg") && svg.contains("<path"));
+    }
+
+    #[test]
+    fn streamed_svg_matches_the_string() {
+        let build = || {
+            let mut path_builder = Path::builder();
+            path_builder.begin(Point2D::new(0.1, 0.25));
+            path_builder.quadratic_bezier_to(Point2D::new(3.0, 1.0), Point2D::new(4.5, 4.0));
+            path_builder.cubic_bezier_to(
+                Point2D::new(1.0, 5.0),
+                Point2D::new(-1.0 / 3.0, 2.0),
+                Point2D::new(0.0, 4.0),
+            );
+            path_builder.end(true);
+            let path = path_builder.build();
+            let mut writer = LyonWriter::new();
+            let hatching = writer
+                .define_pattern(
+                    &path,
+                    4.0,
+                    4.0,
+                    None,
+                    Some(stroke(Color::black(), 1.0, 0.5)),
+                )
+                .unwrap();
+            writer
+                .push(&path, Some(fill_pattern(&hatching, 1.0)), None, None)
+                .unwrap();
+            writer.layer("top");
+            for i in 0..3 {
+                writer
+                    .push(
+                        &path,
+                        Some(fill(Color::white(), 0.5)),
+                        None,
+                        Some(SvgTransform::from_translate(i as f32, 0.0)),
+                    )
+                    .unwrap();
+            }
+            writer
+        };
+        let mut buffer = Vec::new();
+        build().write_to(&mut buffer).unwrap();
+        let streamed = String::from_utf8(buffer).unwrap();
+        assert!(!streamed.contains("16777215"));
+        assert_eq!(streamed, build().to_svg_string().unwrap());
+    }
+
+    #[test]
+    fn paths_with_the_same_style_are_merged() {
+        let mut writer = LyonWriter::new().with_merged_paths();
+        for i in 0..3 {
+            let mut path_builder = Path::builder();
+            path_builder.begin(Point2D::new(i as f32, 0.0));
+            path_builder.line_to(Point2D::new(i as f32, 1.0));
+            path_builder.end(false);
+            writer
+                .push(
+                    &path_builder.build(),
+                    None,
+                    Some(stroke(Color::black(), 1.0, 0.1)),
+                    None,
+                )
+                .unwrap();
+        }
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::new(0.0, 2.0));
+        path_builder.line_to(Point2D::new(2.0, 2.0));
+        path_builder.end(false);
+        let line = path_builder.build();
+        writer
+            .push(&line, None, Some(stroke(Color::white(), 1.0, 0.1)), None)
+            .unwrap();
+        writer
+            .push_with(
+                &line,
+                PathProps::new()
+                    .stroke(stroke(Color::white(), 1.0, 0.1))
+                    .id("kept"),
+            )
+            .unwrap();
+        let svg = writer.to_svg_string().unwrap();
+        assert_eq!(svg.matches("<path").count(), 3);
+        assert!(svg.contains("d=\"M 0 0 L 0 1 M 1 0 L 1 1 M 2 0 L 2 1\""));
+        assert!(svg.contains("id=\"kept\""));
+    }
+
+    #[test]
+    fn identical_paths_share_their_data() {
         let mut path_builder = Path::builder();
         path_builder.begin(Point2D::origin());
         path_builder.line_to(Point2D::new(1.0, 1.0));
-        path_builder.quadratic_bezier_to(Point2D::new(2.0, 1.0), Point2D::new(3.0, 2.0));
+        path_builder.line_to(Point2D::new(2.0, 0.0));
         path_builder.end(true);
-        let path = path_builder.build();
+        let marker = path_builder.build();
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 1.0));
+        path_builder.end(false);
+        let line = path_builder.build();
+        let build = || {
+            let mut writer = LyonWriter::new().with_shared_paths();
+            writer
+                .push_with(&marker, PathProps::new().id("first"))
+                .unwrap();
+            writer
+                .push(
+                    &marker,
+                    Some(fill(Color::black(), 1.0)),
+                    None,
+                    Some(SvgTransform::from_translate(3.0, 0.0)),
+                )
+                .unwrap();
+            writer
+                .push(&line, None, Some(stroke(Color::black(), 1.0, 1.0)), None)
+                .unwrap();
+            writer
+        };
+        let mut writer = build();
+        writer.switch_nodes(None);
+        assert!(matches!(
+            (&*writer.nodes[0].borrow(), &*writer.nodes[1].borrow()),
+            (NodeKind::Path(a), NodeKind::Path(b)) if Rc::ptr_eq(&a.data, &b.data)
+        ));
+        let svg = build().to_svg_string().unwrap();
         assert!(
-            lyon_path_to_svg_with_attributes(&path, None, None, None)
-                .unwrap()
-                .data
-                .len()
-                == 5
+            svg.contains("<defs><path id=\"roarsvg-shape1\" d=\"M 0 0 L 1 1 L 2 0 Z\"/></defs>")
         );
+        assert!(svg.contains(
+            "<use id=\"first\" fill=\"none\" stroke=\"none\" href=\"#roarsvg-shape1\"/>"
+        ));
+        assert_eq!(svg.matches("<use").count(), 2);
+        assert_eq!(svg.matches("<path").count(), 2);
+        let mut buffer = Vec::new();
+        build().write_to(&mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), svg);
+    }
+
+    #[test]
+    fn streamed_path_data_is_not_mistaken_for_placeholders() {
+        // the shared definition starts as the placeholders of the streamed path data
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::new(16777215.0, -16777215.0));
+        path_builder.line_to(Point2D::new(0.0, 2.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::new(0.0, 0.0));
+        path_builder.line_to(Point2D::new(4.0, 1.0));
+        path_builder.end(false);
+        let line = path_builder.build();
+        let build = || {
+            let mut writer = LyonWriter::new().with_shared_paths();
+            for _ in 0..2 {
+                writer
+                    .push(&path, None, Some(stroke(Color::black(), 1.0, 1.0)), None)
+                    .unwrap();
+            }
+            // the path data of the third placeholder
+            writer
+                .push(&line, None, Some(stroke(Color::black(), 1.0, 1.0)), None)
+                .unwrap();
+            writer
+        };
+        let svg = build().to_svg_string().unwrap();
+        assert!(svg.contains("d=\"M 16777215 -16777215 L 0 2\""));
+        let mut buffer = Vec::new();
+        build().write_to(&mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), svg);
     }
+
+    #[cfg(feature = "flate2")]
     #[test]
-    fn writing_does_not_panic() {
-        let file_path = "tmpthis.svg";
+    fn svgz_is_gzipped() {
+        use std::io::Read;
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
         let mut writer = LyonWriter::new();
+        writer
+            .push(
+                &path_builder.build(),
+                None,
+                Some(stroke(Color::black(), 1.0, 1.0)),
+                None,
+            )
+            .unwrap();
+        let file_path = std::env::temp_dir().join("roarsvg_svgz_is_gzipped.svgz");
+        writer.write_svgz(&file_path).unwrap();
+        let mut svg = String::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(&file_path).unwrap())
+            .read_to_string(&mut svg)
+            .unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+        assert!(svg.starts_with("<svg"));
+    }
 
+    #[cfg(feature = "raster")]
+    #[test]
+    fn png_is_rendered_at_scale() {
         let mut path_builder = Path::builder();
         path_builder.begin(Point2D::origin());
-        path_builder.line_to(Point2D::new(1.0, 1.0));
-        path_builder.quadratic_bezier_to(Point2D::new(2.0, 1.0), Point2D::new(3.0, 2.0));
-        path_builder.cubic_bezier_to(
-            Point2D::new(2.0, 1.0),
-            Point2D::new(5.0, 1.0),
-            Point2D::new(3.0, 2.0),
-        );
+        path_builder.line_to(Point2D::new(4.0, 0.0));
+        path_builder.line_to(Point2D::new(4.0, 2.0));
         path_builder.end(true);
-        let path = path_builder.build();
+        let mut writer = LyonWriter::new();
         writer
             .push(
-                &path,
+                &path_builder.build(),
+                Some(fill(Color::black(), 1.0)),
+                None,
                 None,
-                Some(stroke(Color::new_rgb(253, 77, 44), 0.8, 2.0)),
-                Some(SvgTransform::from_translate(0.0, 0.0)),
             )
-            .expect("Path 1 should be writable!");
+            .unwrap();
+        let file_path = std::env::temp_dir().join("roarsvg_png_is_rendered_at_scale.png");
+        writer.write_png(&file_path, 2.0).unwrap();
+        let png = std::fs::read(&file_path).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        // width and height of the IHDR chunk
+        assert_eq!(&png[16..24], &[0, 0, 0, 8, 0, 0, 0, 4]);
+    }
+
+    #[cfg(feature = "svg2pdf")]
+    #[test]
+    fn pdf_is_written() {
         let mut path_builder = Path::builder();
         path_builder.begin(Point2D::origin());
-        path_builder.cubic_bezier_to(
-            Point2D::new(2.0, 1.0),
-            Point2D::new(5.0, 1.0),
-            Point2D::new(3.0, 2.0),
-        );
+        path_builder.line_to(Point2D::new(4.0, 0.0));
+        path_builder.line_to(Point2D::new(4.0, 2.0));
         path_builder.end(true);
-        let path = path_builder.build();
+        let mut writer = LyonWriter::new();
         writer
             .push(
-                &path,
+                &path_builder.build(),
+                Some(fill(Color::black(), 1.0)),
+                None,
+                None,
+            )
+            .unwrap();
+        let file_path = std::env::temp_dir().join("roarsvg_pdf_is_written.pdf");
+        writer.write_pdf(&file_path).unwrap();
+        let pdf = std::fs::read(&file_path).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn data_uris_are_percent_encoded() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
+        let mut writer = LyonWriter::new();
+        writer
+            .push(
+                &path_builder.build(),
                 None,
                 Some(stroke(Color::black(), 1.0, 1.0)),
-                Some(SvgTransform::from_translate(2.0, 2.0)),
+                None,
             )
-            .expect("Path 2 should be writable!");
-        writer.write(file_path).expect("Writing should not panic!");
+            .unwrap();
+        let uri = writer.to_data_uri().unwrap();
+        assert!(uri.starts_with("data:image/svg+xml,%3Csvg%20width=%224.7"));
+        assert!(!uri.contains(['<', '>', '"', '#', ' ', '\n']));
+        assert_eq!(data_uri("ñ"), "data:image/svg+xml,%C3%B1");
+    }
 
-        std::fs::remove_file(file_path).unwrap();
+    #[test]
+    fn tree_can_be_post_processed() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
+        let mut writer = LyonWriter::new();
+        writer
+            .push(
+                &path_builder.build(),
+                None,
+                Some(stroke(Color::black(), 1.0, 1.0)),
+                None,
+            )
+            .unwrap();
+        let tree = writer.into_tree().unwrap();
+        // the butt caps of the diagonal line stick out of its ends
+        assert!((tree.size.width() - (4.0 + 0.5f32.sqrt())).abs() < 1e-4);
+        assert_eq!(tree.root.descendants().count(), 3);
     }
 
     #[test]
-    fn path_and_texts_do_not_panic() {
-        let file_path = "textex.svg";
+    fn d_strings_close_subpaths() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.quadratic_bezier_to(Point2D::new(1.0, 1.0), Point2D::new(2.0, 0.0));
+        path_builder.end(true);
+        assert_eq!(path_to_d(&path_builder.build()), "M 0 0 Q 1 1 2 0 Z");
+        assert_eq!(path_to_d(&Path::new()), "");
+    }
+
+    #[test]
+    fn d_strings_are_formatted_as_usvg_does() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(0.000000001, 123456.79));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let d = "d=\"M 0 0 L 0 123456.79\"";
         let mut writer = LyonWriter::new();
-        // push the created path with some fill and stroke, in the origin
+        writer.push(&path, None, None, None).unwrap();
+        assert!(writer.to_svg_string().unwrap().contains(d));
+        let mut writer = LyonWriter::new().with_shared_paths();
+        for _ in 0..2 {
+            writer.push(&path, None, None, None).unwrap();
+        }
+        assert!(writer.to_svg_string().unwrap().contains(d));
+        assert_eq!(path_to_d(&path), "M 0 0 L 0 123456.79");
+    }
+
+    #[test]
+    fn grids_have_a_bounded_number_of_lines() {
+        let mut writer = LyonWriter::new();
+        let black = stroke(Color::black(), 1.0, 1.0);
+        let rect = (0.0, 0.0, 1000.0, 1000.0);
+        assert!(writer
+            .push_grid(rect, 1e-9, 1.0, black.clone(), None)
+            .is_err());
+        assert!(writer
+            .push_grid(rect, 1.0, 1e-9, black.clone(), None)
+            .is_err());
+        writer.push_grid(rect, 0.01, 0.01, black, None).unwrap();
+    }
+
+    #[test]
+    fn layers_are_written_to_their_own_files() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(4.0, 4.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::new(8.0, 8.0));
+        path_builder.line_to(Point2D::new(10.0, 10.0));
+        path_builder.end(false);
+        let far_path = path_builder.build();
+        // round caps stick out half of the width from the ends of the lines
+        let line = Some(Stroke {
+            linecap: usvg::LineCap::Round,
+            ..stroke(Color::black(), 1.0, 1.0)
+        });
+        let mut writer = LyonWriter::new();
+        writer.layer("back ground");
+        writer.push(&path, None, line.clone(), None).unwrap();
+        writer.layer("back/ground");
+        writer.push(&far_path, None, line, None).unwrap();
+        let dir = std::env::temp_dir().join("roarsvg_layers_are_written");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_paths = writer.write_layers(&dir).unwrap();
+        assert_eq!(
+            file_paths,
+            vec![dir.join("back_ground.svg"), dir.join("back_ground-2.svg")]
+        );
+        let svgs: Vec<String> = file_paths
+            .iter()
+            .map(|file_path| std::fs::read_to_string(file_path).unwrap())
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        for svg in &svgs {
+            assert!(svg.contains("width=\"11\" height=\"11\""));
+            assert_eq!(svg.matches("<path").count(), 1);
+        }
+        assert!(svgs[0].contains("inkscape:label=\"back ground\""));
+    }
+
+    #[test]
+    fn nodes_keep_their_push_order() {
         let mut path_builder = Path::builder();
         path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(true);
+        let path = path_builder.build();
+        let build = |ordering| {
+            let mut writer = LyonWriter::new().with_ordering(ordering);
+            writer
+                .push_path_group(
+                    [&path],
+                    None,
+                    None,
+                    SvgTransform::default(),
+                    Some("group".to_string()),
+                )
+                .unwrap();
+            writer
+                .push_with(
+                    &path,
+                    PathProps::new()
+                        .fill(fill(Color::black(), 1.0))
+                        .id("filled"),
+                )
+                .unwrap();
+            writer
+                .push_with(&path, PathProps::new().id("bare"))
+                .unwrap();
+            writer.to_svg_string().unwrap()
+        };
+        let svg = build(Ordering::Insertion);
+        let (group, filled, bare) = (
+            svg.find("id=\"group\"").unwrap(),
+            svg.find("id=\"filled\"").unwrap(),
+            svg.find("id=\"bare\"").unwrap(),
+        );
+        assert!(group < filled && filled < bare);
+        let svg = build(Ordering::StyleSorted);
+        let (group, filled, bare) = (
+            svg.find("id=\"group\"").unwrap(),
+            svg.find("id=\"filled\"").unwrap(),
+            svg.find("id=\"bare\"").unwrap(),
+        );
+        assert!(bare < filled && filled < group);
+    }
+
+    #[test]
+    fn bounds_are_tight_around_curves() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::new(10.0, 10.0));
         path_builder.cubic_bezier_to(
-            Point2D::new(2.0, 1.0),
-            Point2D::new(5.0, 1.0),
-            Point2D::new(3.0, 2.0),
+            Point2D::new(10.0, 20.0),
+            Point2D::new(20.0, 20.0),
+            Point2D::new(20.0, 10.0),
         );
+        path_builder.end(false);
+        let path = path_builder.build();
+        let mut writer = LyonWriter::new();
+        writer.push(&path, None, None, None).unwrap();
+        // a quarter turn, so that the control points stick out to the left
+        let rotation = SvgTransform::from_row(0.0, 1.0, -1.0, 0.0, 0.0, 0.0);
+        writer.push(&path, None, None, Some(rotation)).unwrap();
+        let svg = writer.to_svg_string().unwrap();
+        assert!(svg.contains("viewBox=\"-17.5 10 37.5 10\""));
+    }
+
+    #[test]
+    fn bounds_follow_node_and_global_transforms() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(10.0, 0.0));
+        path_builder.line_to(Point2D::new(10.0, 10.0));
         path_builder.end(true);
         let path = path_builder.build();
-        writer
-            .push(
-                &path,
+        let shifted = Some(SvgTransform::from_translate(500.0, 500.0));
+        let mut writer = LyonWriter::new().with_transform(SvgTransform::from_scale(2.0, 2.0));
+        writer.push(&path, None, None, shifted).unwrap();
+        let svg = writer.to_svg_string().unwrap();
+        assert!(svg.contains("viewBox=\"1000 1000 20 20\""));
+        // a quarter turn of the whole document
+        let rotation = SvgTransform::from_row(0.0, 1.0, -1.0, 0.0, 0.0, 0.0);
+        let mut writer = LyonWriter::new().with_transform(rotation);
+        writer.push(&path, None, None, shifted).unwrap();
+        let svg = writer.to_svg_string().unwrap();
+        assert!(svg.contains("viewBox=\"-510 500 10 10\""));
+    }
+
+    #[test]
+    fn empty_writers_are_blank_svgs() {
+        let svg = LyonWriter::new().to_svg_string().unwrap();
+        assert!(svg.starts_with("<svg width=\"256\" height=\"256\" viewBox=\"0 0 256 256\""));
+        let svg = LyonWriter::new()
+            .with_size(10.0, 20.0)
+            .to_svg_string()
+            .unwrap();
+        assert!(svg.contains("viewBox=\"0 0 10 20\""));
+    }
+
+    #[test]
+    fn non_finite_points_are_reported() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::new(1.0, 1.0));
+        path_builder.line_to(Point2D::new(2.0, 2.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        // the builder asserts finite points, but transforms can produce others
+        let nan_path =
+            path.clone()
+                .transformed(&lyon_path::geom::euclid::default::Transform2D::scale(
+                    1.0,
+                    f32::NAN,
+                ));
+        let mut writer = LyonWriter::new();
+        writer.push(&path, None, None, None).unwrap();
+        let error = writer
+            .push_many([&path, &nan_path], None, None, None)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            LyonTranslationError::NonFiniteCoordinate { node: 2, x, y } if x == 1.0 && y.is_nan()
+        ));
+        let mut writer = LyonWriter::new().with_lazy_translation();
+        assert!(matches!(
+            writer.push(&nan_path, None, None, None),
+            Err(LyonTranslationError::NonFiniteCoordinate { node: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn tracked_bounds_match_a_full_scan() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::new(-3.0, 2.0));
+        path_builder.line_to(Point2D::new(2.0, 5.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let build = |writer: LyonWriter<NoText>, tracked: bool| {
+            let mut writer = writer;
+            writer
+                .push(&path, None, None, Some(SvgTransform::from_scale(2.0, 1.0)))
+                .unwrap();
+            writer
+                .group(SvgTransform::from_translate(0.0, -10.0), |g| {
+                    g.push(&path, None, None, None)?;
+                    Ok(())
+                })
+                .unwrap();
+            writer
+                .artboard("board", (0.0, 0.0, 4.0, 4.0), (0.0, 0.0, 100.0, 100.0))
+                .unwrap();
+            writer.extend([(path.clone(), None, None, None)]);
+            writer.layer("top");
+            writer.extend([(
+                path.clone(),
                 None,
-                Some(stroke(Color::black(), 1.0, 1.0)),
-                Some(SvgTransform::from_translate(2.0, 2.0)),
-            )
-            .expect("Path 1 should be writable!");
-        let mut fontdb = usvg::fontdb::Database::new();
-        fontdb.load_system_fonts();
-        let mut writer = writer.add_fonts(fontdb);
+                None,
+                Some(SvgTransform::from_translate(9.0, 0.0)),
+            )]);
+            if !tracked {
+                writer.bounds = None;
+            }
+            writer.to_svg_string().unwrap()
+        };
+        let svg = build(LyonWriter::new(), true);
+        assert!(svg.contains("viewBox=\"-6 -8 17 13\""));
+        assert_eq!(svg, build(LyonWriter::new(), false));
+        assert_eq!(svg, build(LyonWriter::new().with_lazy_translation(), true));
+    }
+
+    #[test]
+    fn lazy_writers_translate_on_write() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let mut empty_builder = Path::builder();
+        empty_builder.begin(Point2D::origin());
+        empty_builder.end(false);
+        let empty = empty_builder.build();
+        let build = |writer: LyonWriter<NoText>| {
+            let mut writer = writer;
+            writer
+                .push(&path, None, Some(stroke(Color::black(), 1.0, 0.1)), None)
+                .unwrap();
+            writer
+                .group(SvgTransform::from_translate(1.0, 0.0), |g| {
+                    g.push_with(&path, PathProps::new().opacity(0.5).id("half"))?;
+                    g.extend([(empty.clone(), None, None, None)]);
+                    Ok(())
+                })
+                .unwrap();
+            writer.extend([
+                (empty.clone(), None, None, None),
+                (path.clone(), Some(fill(Color::white(), 1.0)), None, None),
+            ]);
+            writer.to_svg_string().unwrap()
+        };
+        assert_eq!(
+            build(LyonWriter::new().with_lazy_translation()),
+            build(LyonWriter::new())
+        );
+        let mut writer = LyonWriter::new().with_lazy_translation();
+        writer.push(&empty, None, None, None).unwrap();
+        assert!(matches!(
+            writer.to_svg_string(),
+            Err(LyonTranslationError::SvgFailure)
+        ));
+    }
+
+    #[test]
+    fn artboards_are_nested_svgs() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(2.0, 1.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+        let mut writer = LyonWriter::new();
+        assert!(writer
+            .artboard("empty", (0.0, 0.0, 0.0, 1.0), (0.0, 0.0, 1.0, 1.0))
+            .is_err());
         writer
-            .push_text(
-                "hello".to_string(),
-                vec!["Arial".to_string()],
-                12.0,
-                SvgTransform::from_translate(0., 0.),
-                Some(fill(usvg::Color::black(), 1.0)),
-                Some(stroke(usvg::Color::black(), 1.0, 1.0)),
-            )
-            .expect("Text should be writable!");
-        // finally, write the SVG, Text with be converted to SvgPath
-        writer.write(file_path).expect("Writing should not panic!");
-        std::fs::remove_file(file_path).unwrap();
+            .artboard("left", (0.0, 0.0, 40.0, 40.0), (0.0, 0.0, 2.0, 1.0))
+            .unwrap();
+        writer
+            .push(&path, None, Some(stroke(Color::black(), 1.0, 0.1)), None)
+            .unwrap();
+        writer
+            .artboard("right", (40.0, 0.0, 40.0, 40.0), (0.0, 0.0, 2.0, 1.0))
+            .unwrap();
+        writer.exit_layer();
+        let transform = artboard_transform(
+            NonZeroRect::from_xywh(0.0, 0.0, 40.0, 40.0).unwrap(),
+            NonZeroRect::from_xywh(0.0, 0.0, 2.0, 1.0).unwrap(),
+        );
+        assert_eq!(
+            transform,
+            SvgTransform::from_row(20.0, 0.0, 0.0, 20.0, 0.0, 10.0)
+        );
+        let svg = writer.prepare().unwrap().to_svg_string();
+        assert!(svg.contains("viewBox=\"0 0 80 40\""));
+        assert!(svg.contains(
+            "<svg id=\"roarsvg-layer1\" x=\"0\" y=\"0\" width=\"40\" height=\"40\" \
+             viewBox=\"0 0 2 1\">"
+        ));
+        assert!(svg.contains("<svg id=\"roarsvg-layer2\" x=\"40\""));
+        assert_eq!(svg.matches("</svg>").count(), 2);
+        assert!(!svg.contains("matrix(20"));
+    }
+
+    #[test]
+    fn empty_paths_are_skipped_on_extend() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(Point2D::origin());
+        path_builder.line_to(Point2D::new(1.0, 1.0));
+        path_builder.end(false);
+        let path = path_builder.build();
+
+        let mut writer: LyonWriter<NoText> = [
+            (path.clone(), None, None, None),
+            (Path::new(), None, None, None),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(writer.nodes.len(), 1);
+        writer.extend([(path, Some(fill(Color::black(), 1.0)), None, None)]);
+        assert_eq!(writer.nodes.len(), 2);
     }
 }
@@ -0,0 +1,431 @@
+//! Paint servers (gradients and patterns) managed by the [`LyonWriter`].
+use std::rc::Rc;
+use std::sync::Arc;
+
+use lyon_path::math::Point;
+use lyon_path::Path;
+use usvg::{
+    AspectRatio, BaseGradient, Group, Image, ImageKind, ImageRendering, LinearGradient, NodeKind,
+    NonZeroRect, Opacity, Paint, Pattern, PositiveF32, RadialGradient, SpreadMethod, Stop,
+    StopOffset, Transform, Units, ViewBox, Visibility,
+};
+
+use crate::{
+    lyon_path_to_svg_with_attributes, Color, Fill, LyonTranslationError, LyonWriter, Stroke,
+};
+
+/// Handle to a pattern defined with [`LyonWriter::define_pattern`].
+///
+/// Use it with [`fill_pattern`] or [`PatternId::paint`].
+#[derive(Clone, Debug)]
+pub struct PatternId(Rc<Pattern>);
+
+impl PatternId {
+    /// [`Paint`] of the pattern, to be used in a [`Fill`] or [`Stroke`].
+    pub fn paint(&self) -> Paint {
+        Paint::Pattern(self.0.clone())
+    }
+}
+
+/// Utility function to build a [`Fill`] with a pattern.
+pub fn fill_pattern(pattern: &PatternId, opacity: f32) -> Fill {
+    Fill {
+        paint: pattern.paint(),
+        opacity: Opacity::new_clamped(opacity),
+        ..Default::default()
+    }
+}
+
+/// Color stop of a gradient.
+///
+/// Built from an `offset` and a [`Color`], with an optional opacity. Tuples of
+/// `(offset, color)` and `(offset, color, opacity)` can be used in its place.
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{Color, GradientStop};
+///
+/// let stops = [
+///     GradientStop::new(0.0, Color::black()),
+///     GradientStop::new(1.0, Color::white()).opacity(0.2),
+/// ];
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradientStop {
+    offset: f32,
+    color: Color,
+    opacity: f32,
+}
+
+impl GradientStop {
+    /// Opaque stop of `color` at `offset` (clamped to `[0, 1]`).
+    pub fn new(offset: f32, color: Color) -> Self {
+        Self {
+            offset,
+            color,
+            opacity: 1.0,
+        }
+    }
+
+    /// Set the opacity of the stop (clamped to `[0, 1]`).
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+}
+
+impl From<(f32, Color)> for GradientStop {
+    fn from((offset, color): (f32, Color)) -> Self {
+        GradientStop::new(offset, color)
+    }
+}
+
+impl From<(f32, Color, f32)> for GradientStop {
+    fn from((offset, color, opacity): (f32, Color, f32)) -> Self {
+        GradientStop::new(offset, color).opacity(opacity)
+    }
+}
+
+/// Options of a gradient defined with [`LyonWriter::linear_gradient_with`] or
+/// [`LyonWriter::radial_gradient_with`].
+///
+/// The default is a gradient in user space units, with no transform and padded with
+/// the colors of its ends.
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{GradientOptions, SpreadMethod, SvgTransform, Units};
+///
+/// let options = GradientOptions::new()
+///     .transform(SvgTransform::from_rotate(45.0))
+///     .spread_method(SpreadMethod::Reflect)
+///     .units(Units::ObjectBoundingBox);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradientOptions {
+    transform: Transform,
+    spread_method: SpreadMethod,
+    units: Units,
+}
+
+impl Default for GradientOptions {
+    fn default() -> Self {
+        Self {
+            transform: Transform::default(),
+            spread_method: SpreadMethod::Pad,
+            units: Units::UserSpaceOnUse,
+        }
+    }
+}
+
+impl GradientOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `gradientTransform`, applied to the gradient coordinates.
+    pub fn transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Set how the gradient is painted outside of its ends.
+    pub fn spread_method(mut self, spread_method: SpreadMethod) -> Self {
+        self.spread_method = spread_method;
+        self
+    }
+
+    /// Set the coordinate system of the gradient points: the user space of the element
+    /// that uses the paint or fractions of its bounding box.
+    pub fn units(mut self, units: Units) -> Self {
+        self.units = units;
+        self
+    }
+}
+
+impl<T> LyonWriter<T> {
+    /// Build a linear gradient [`Paint`] from `start` to `end`, with the color `stops`
+    /// (see [`GradientStop`]) sorted by offset.
+    ///
+    /// The points are in the user space of the element that uses the paint. The writer
+    /// assigns an unique id to the gradient, which is written to the `<defs>` of the SVG.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, Fill, LyonWriter};
+    /// use lyon_path::math::point;
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// let paint = writer.linear_gradient(
+    ///     [(0.0, Color::black()), (1.0, Color::white())],
+    ///     point(0.0, 0.0),
+    ///     point(10.0, 0.0),
+    /// );
+    /// let fill = Fill::from_paint(paint);
+    /// ```
+    pub fn linear_gradient(
+        &mut self,
+        stops: impl IntoIterator<Item = impl Into<GradientStop>>,
+        start: Point,
+        end: Point,
+    ) -> Paint {
+        self.linear_gradient_with(stops, start, end, GradientOptions::default())
+    }
+
+    /// Same as [`LyonWriter::linear_gradient`] with the given [`GradientOptions`].
+    pub fn linear_gradient_with(
+        &mut self,
+        stops: impl IntoIterator<Item = impl Into<GradientStop>>,
+        start: Point,
+        end: Point,
+        options: GradientOptions,
+    ) -> Paint {
+        Paint::LinearGradient(Rc::new(LinearGradient {
+            id: self.fresh_id("linear-gradient"),
+            x1: start.x,
+            y1: start.y,
+            x2: end.x,
+            y2: end.y,
+            base: base_gradient(stops, options),
+        }))
+    }
+
+    /// Build a radial gradient [`Paint`] with the circle at `center` of `radius` as its end
+    /// and starting at the `focal` point (`center` if `None`), with the color `stops`
+    /// (see [`GradientStop`]) sorted by offset.
+    ///
+    /// The points are in the user space of the element that uses the paint. The writer
+    /// assigns an unique id to the gradient, which is written to the `<defs>` of the SVG.
+    ///
+    /// # Errors
+    ///
+    /// [`LyonTranslationError::SvgFailure`] if `radius` is negative or not finite.
+    pub fn radial_gradient(
+        &mut self,
+        center: Point,
+        radius: f32,
+        focal: Option<Point>,
+        stops: impl IntoIterator<Item = impl Into<GradientStop>>,
+    ) -> Result<Paint, LyonTranslationError> {
+        self.radial_gradient_with(center, radius, focal, stops, GradientOptions::default())
+    }
+
+    /// Same as [`LyonWriter::radial_gradient`] with the given [`GradientOptions`].
+    ///
+    /// # Errors
+    ///
+    /// [`LyonTranslationError::SvgFailure`] if `radius` is negative or not finite.
+    pub fn radial_gradient_with(
+        &mut self,
+        center: Point,
+        radius: f32,
+        focal: Option<Point>,
+        stops: impl IntoIterator<Item = impl Into<GradientStop>>,
+        options: GradientOptions,
+    ) -> Result<Paint, LyonTranslationError> {
+        let focal = focal.unwrap_or(center);
+        Ok(Paint::RadialGradient(Rc::new(RadialGradient {
+            r: PositiveF32::new(radius).ok_or(LyonTranslationError::SvgFailure)?,
+            id: self.fresh_id("radial-gradient"),
+            cx: center.x,
+            cy: center.y,
+            fx: focal.x,
+            fy: focal.y,
+            base: base_gradient(stops, options),
+        })))
+    }
+
+    /// Define a pattern that repeats the `tile` path, styled with `fill` and `stroke`, every
+    /// `width` and `height` in user space.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill_pattern, stroke};
+    /// use lyon_path::Path;
+    /// use lyon_path::math::point;
+    ///
+    /// // diagonal hatching
+    /// let mut tile_builder = Path::builder();
+    /// tile_builder.begin(point(0.0, 4.0));
+    /// tile_builder.line_to(point(4.0, 0.0));
+    /// tile_builder.end(false);
+    /// let tile = tile_builder.build();
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// let hatching = writer
+    ///     .define_pattern(&tile, 4.0, 4.0, None, Some(stroke(Color::black(), 1.0, 0.5)))
+    ///     .expect("Pattern should be valid!");
+    /// let hatched = fill_pattern(&hatching, 1.0);
+    /// ```
+    pub fn define_pattern(
+        &mut self,
+        tile: &Path,
+        width: f32,
+        height: f32,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+    ) -> Result<PatternId, LyonTranslationError> {
+        let tile = NodeKind::Path(
+            lyon_path_to_svg_with_attributes(tile, fill, stroke, None)
+                .ok_or(LyonTranslationError::SvgFailure)?,
+        );
+        self.pattern_from_tile(tile, width, height)
+    }
+
+    /// Define a pattern that repeats a raster image (PNG or JPEG, detected from `data`)
+    /// every `width` and `height` in user space, scaling the image to fit that tile.
+    ///
+    /// The image is embedded as base64 in the pattern definition.
+    ///
+    /// # Errors
+    ///
+    /// [`LyonTranslationError::SvgFailure`] if `data` is neither PNG nor JPEG.
+    pub fn define_image_pattern(
+        &mut self,
+        data: &[u8],
+        width: f32,
+        height: f32,
+    ) -> Result<PatternId, LyonTranslationError> {
+        let kind = if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            ImageKind::PNG(Arc::new(data.to_vec()))
+        } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            ImageKind::JPEG(Arc::new(data.to_vec()))
+        } else {
+            return Err(LyonTranslationError::SvgFailure);
+        };
+        let tile = NodeKind::Image(Image {
+            id: String::new(),
+            transform: Transform::default(),
+            visibility: Visibility::Visible,
+            view_box: ViewBox {
+                rect: tile_rect(width, height)?,
+                aspect: AspectRatio::default(),
+            },
+            rendering_mode: ImageRendering::default(),
+            kind,
+        });
+        self.pattern_from_tile(tile, width, height)
+    }
+
+    fn pattern_from_tile(
+        &mut self,
+        tile: NodeKind,
+        width: f32,
+        height: f32,
+    ) -> Result<PatternId, LyonTranslationError> {
+        let rect = tile_rect(width, height)?;
+        let root = usvg::Node::new(NodeKind::Group(Group::default()));
+        root.append(usvg::Node::new(tile));
+        Ok(PatternId(Rc::new(Pattern {
+            id: self.fresh_id("pattern"),
+            units: Units::UserSpaceOnUse,
+            content_units: Units::UserSpaceOnUse,
+            transform: Transform::default(),
+            rect,
+            view_box: None,
+            root,
+        })))
+    }
+}
+
+fn tile_rect(width: f32, height: f32) -> Result<NonZeroRect, LyonTranslationError> {
+    NonZeroRect::from_xywh(0.0, 0.0, width, height).ok_or(LyonTranslationError::WrongBoundingBox {
+        min_x: 0.0,
+        max_x: width,
+        min_y: 0.0,
+        max_y: height,
+    })
+}
+
+/// Gradient with the given stops and options.
+fn base_gradient(
+    stops: impl IntoIterator<Item = impl Into<GradientStop>>,
+    options: GradientOptions,
+) -> BaseGradient {
+    BaseGradient {
+        units: options.units,
+        transform: options.transform,
+        spread_method: options.spread_method,
+        stops: to_stops(stops),
+    }
+}
+
+/// Validate the stops: offsets and opacities are clamped to `[0, 1]` (non-finite offsets
+/// become 0) and stops are (stably) sorted by offset.
+fn to_stops(stops: impl IntoIterator<Item = impl Into<GradientStop>>) -> Vec<Stop> {
+    let mut stops: Vec<Stop> = stops
+        .into_iter()
+        .map(|stop| {
+            let stop = stop.into();
+            Stop {
+                offset: StopOffset::new_clamped(if stop.offset.is_finite() {
+                    stop.offset
+                } else {
+                    0.0
+                }),
+                color: stop.color,
+                opacity: Opacity::new_clamped(stop.opacity),
+            }
+        })
+        .collect();
+    stops.sort_by(|a, b| a.offset.get().total_cmp(&b.offset.get()));
+    stops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_patterns_need_a_known_format() {
+        let mut writer = LyonWriter::new();
+        assert!(writer.define_image_pattern(b"GIF89a", 2.0, 2.0).is_err());
+        let pattern = writer
+            .define_image_pattern(b"\xFF\xD8\xFF\xE0", 2.0, 2.0)
+            .expect("JPEG should be accepted!");
+        let child = pattern.0.root.first_child().unwrap();
+        assert!(matches!(
+            *child.borrow(),
+            NodeKind::Image(Image {
+                kind: ImageKind::JPEG(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn gradient_options_are_kept() {
+        let mut writer = LyonWriter::new();
+        let options = GradientOptions::new()
+            .spread_method(SpreadMethod::Repeat)
+            .units(Units::ObjectBoundingBox);
+        let paint = writer.linear_gradient_with(
+            [(0.0, Color::black()), (1.0, Color::white())],
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            options,
+        );
+        let Paint::LinearGradient(gradient) = paint else {
+            panic!("A linear gradient should be built!");
+        };
+        assert_eq!(gradient.spread_method, SpreadMethod::Repeat);
+        assert_eq!(gradient.units, Units::ObjectBoundingBox);
+    }
+
+    #[test]
+    fn stops_are_clamped_and_sorted() {
+        let stops = to_stops([
+            GradientStop::new(1.5, Color::white()),
+            GradientStop::new(f32::NAN, Color::black()).opacity(-1.0),
+            GradientStop::new(0.5, Color::new_rgb(255, 0, 0)),
+        ]);
+        let offsets: Vec<f32> = stops.iter().map(|stop| stop.offset.get()).collect();
+        assert_eq!(offsets, vec![0.0, 0.5, 1.0]);
+        assert_eq!(stops[0].color, Color::black());
+        assert_eq!(stops[0].opacity, Opacity::ZERO);
+    }
+}
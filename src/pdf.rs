@@ -0,0 +1,11 @@
+//! Conversion of the written SVG to PDF with [`svg2pdf`], behind the `svg2pdf` feature.
+use crate::LyonTranslationError;
+
+/// Convert the serialized `svg` to a PDF. Text should be already converted to paths.
+///
+/// The SVG is parsed again, so that the version of [`usvg`] used by [`svg2pdf`] does not
+/// need to match ours.
+pub(crate) fn render_pdf(svg: &str) -> Result<Vec<u8>, LyonTranslationError> {
+    svg2pdf::convert_str(svg, svg2pdf::Options::default())
+        .map_err(|_| LyonTranslationError::SvgFailure)
+}
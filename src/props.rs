@@ -0,0 +1,626 @@
+use std::rc::Rc;
+
+use usvg::tiny_skia_path::Path as PathData;
+use usvg::{
+    BlendMode, Group, LineCap, LineJoin, NodeKind, Opacity, Paint, Path as SvgPath,
+    StrokeMiterlimit, StrokeWidth,
+};
+
+use crate::{
+    normalize_dasharray, ClipId, Color, Fill, FillRule, FilterId, PaintOrder, Stroke, SvgTransform,
+};
+
+/// Style and per-node options of a [`Path`](lyon_path::Path) pushed with
+/// [`LyonWriter::push_with`](crate::LyonWriter::push_with).
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{Color, PathProps, SvgTransform, fill, stroke};
+///
+/// let props = PathProps::new()
+///     .fill(fill(Color::black(), 1.0))
+///     .stroke(stroke(Color::white(), 1.0, 2.0))
+///     .transform(SvgTransform::from_translate(2.0, 2.0))
+///     .opacity(0.5)
+///     .id("node1");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PathProps {
+    pub(crate) fill: Option<Fill>,
+    pub(crate) fill_rule: Option<FillRule>,
+    pub(crate) stroke: Option<Stroke>,
+    pub(crate) paint_order: PaintOrder,
+    pub(crate) transform: Option<SvgTransform>,
+    pub(crate) id: Option<String>,
+    pub(crate) opacity: Option<f32>,
+    pub(crate) clip: Option<ClipId>,
+    pub(crate) filters: Vec<FilterId>,
+    pub(crate) blend_mode: BlendMode,
+    pub(crate) markup: NodeMarkup,
+}
+
+impl PathProps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the [`Fill`] of the path. Paths are not filled by default.
+    pub fn fill(mut self, fill: Fill) -> Self {
+        self.fill = Some(fill);
+        self
+    }
+
+    /// Set the [`FillRule`] of the fill of the path, if any. The rule of the [`Fill`] is
+    /// used by default.
+    pub fn fill_rule(mut self, rule: FillRule) -> Self {
+        self.fill_rule = Some(rule);
+        self
+    }
+
+    /// Set the [`Stroke`] of the path. Paths are not stroked by default.
+    pub fn stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = Some(stroke);
+        self
+    }
+
+    /// Set the [`PaintOrder`] of the path. With [`PaintOrder::StrokeAndFill`], the fill is
+    /// painted over the stroke, so that only the outer half of the stroke is visible.
+    pub fn paint_order(mut self, order: PaintOrder) -> Self {
+        self.paint_order = order;
+        self
+    }
+
+    /// Set the [`SvgTransform`] of the path.
+    pub fn transform(mut self, transform: SvgTransform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// Set the `id` attribute of the written element.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Add `class` to the `class` attribute of the written element, e.g., as a hook for CSS.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, PathProps, fill};
+    /// use lyon_path::math::point;
+    /// use lyon_path::Path;
+    ///
+    /// let mut path = Path::builder();
+    /// path.begin(point(0.0, 0.0));
+    /// path.line_to(point(1.0, 1.0));
+    /// path.line_to(point(2.0, 0.0));
+    /// path.end(true);
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_with(&path.build(), PathProps::new().fill(fill(Color::black(), 1.0)).class("node").class("selected"))
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("class=\"node selected\""));
+    /// ```
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.markup.classes.push(class.into());
+        self
+    }
+
+    /// Set the `<title>` of the path, shown as a tooltip by browsers and read by screen
+    /// readers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, PathProps, fill};
+    /// use lyon_path::math::point;
+    /// use lyon_path::Path;
+    ///
+    /// let mut path = Path::builder();
+    /// path.begin(point(0.0, 0.0));
+    /// path.line_to(point(1.0, 1.0));
+    /// path.line_to(point(2.0, 0.0));
+    /// path.end(true);
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_with(
+    ///         &path.build(),
+    ///         PathProps::new().fill(fill(Color::black(), 1.0)).title("ATP").desc("Adenosine triphosphate"),
+    ///     )
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("><title>ATP</title><desc>Adenosine triphosphate</desc></path>"));
+    /// ```
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.markup.title = Some(title.into());
+        self
+    }
+
+    /// Set the `<desc>` of the path, a longer description for screen readers.
+    pub fn desc(mut self, desc: impl Into<String>) -> Self {
+        self.markup.desc = Some(desc.into());
+        self
+    }
+
+    /// Add the attribute `data-{name}` with `value` to the written element, e.g., to look up
+    /// the node from JavaScript. The value is escaped when written.
+    ///
+    /// # Errors
+    ///
+    /// Pushing the path fails with
+    /// [`LyonTranslationError::InvalidAttributeName`](crate::LyonTranslationError::InvalidAttributeName)
+    /// if `name` is empty, starts with `xml` or has characters other than lowercase ASCII
+    /// letters, digits, `-`, `_` and `.`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonTranslationError, LyonWriter, PathProps, fill};
+    /// use lyon_path::math::point;
+    /// use lyon_path::Path;
+    ///
+    /// let mut path = Path::builder();
+    /// path.begin(point(0.0, 0.0));
+    /// path.line_to(point(1.0, 1.0));
+    /// path.line_to(point(2.0, 0.0));
+    /// path.end(true);
+    /// let path = path.build();
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_with(&path, PathProps::new().fill(fill(Color::black(), 1.0)).data("metabolite", "atp_c"))
+    ///     .expect("Path should be writable!");
+    /// assert!(matches!(
+    ///     writer.push_with(&path, PathProps::new().data("Metabolite", "adp_c")),
+    ///     Err(LyonTranslationError::InvalidAttributeName(_))
+    /// ));
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("data-metabolite=\"atp_c\""));
+    /// ```
+    pub fn data(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.markup.data.push((name.into(), value.into()));
+        self
+    }
+
+    /// Make the path a hyperlink to `href`, so that clicking on it in a browser opens it.
+    ///
+    /// The path is written inside an `<a>` element, which carries the id, the classes, the
+    /// title and the rest of options of the group that would hold the path.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, PathProps, fill};
+    /// use lyon_path::math::point;
+    /// use lyon_path::Path;
+    ///
+    /// let mut path = Path::builder();
+    /// path.begin(point(0.0, 0.0));
+    /// path.line_to(point(1.0, 1.0));
+    /// path.line_to(point(2.0, 0.0));
+    /// path.end(true);
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_with(
+    ///         &path.build(),
+    ///         PathProps::new()
+    ///             .fill(fill(Color::black(), 1.0))
+    ///             .link("https://www.kegg.jp/entry/C00002")
+    ///             .link_target("_blank"),
+    ///     )
+    ///     .expect("Path should be writable!");
+    /// let svg = writer.to_svg_string().expect("Path should be writable!");
+    /// assert!(svg.contains("<a href=\"https://www.kegg.jp/entry/C00002\" target=\"_blank\">"));
+    /// ```
+    pub fn link(mut self, href: impl Into<String>) -> Self {
+        self.markup.link = Some((href.into(), None));
+        self
+    }
+
+    /// Set where the hyperlink of the path is opened (e.g., `_blank` for a new tab). It has
+    /// no effect without a [`link`](Self::link).
+    pub fn link_target(mut self, target: impl Into<String>) -> Self {
+        if let Some((_, link_target)) = self.markup.link.as_mut() {
+            *link_target = Some(target.into());
+        }
+        self
+    }
+
+    /// Set the opacity of the path as a whole (clamped to `[0, 1]`), as opposed to the
+    /// opacities of the fill and stroke.
+    ///
+    /// The path is written inside a group that carries the opacity.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    /// Clip the path with a clip path from [`LyonWriter::define_clip`](crate::LyonWriter::define_clip).
+    ///
+    /// The path is written inside a group that carries the clip path.
+    pub fn clip(mut self, clip: ClipId) -> Self {
+        self.clip = Some(clip);
+        self
+    }
+
+    /// Apply a filter from [`LyonWriter::define_filter`](crate::LyonWriter::define_filter) to
+    /// the path. Filters added in succession are applied in order.
+    ///
+    /// The path is written inside a group that carries the filters.
+    pub fn filter(mut self, filter: FilterId) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Set the [`BlendMode`] with which the path is composited over what is below it.
+    ///
+    /// The path is written inside a group that carries the blend mode.
+    pub fn blend_mode(mut self, mode: BlendMode) -> Self {
+        self.blend_mode = mode;
+        self
+    }
+
+    /// Build the node of the path with `data`, consuming the props.
+    pub(crate) fn into_node(self, data: Rc<PathData>) -> usvg::Node {
+        let mut path = SvgPath::new(data);
+        path.fill = self.fill.map(|fill| Fill {
+            rule: self.fill_rule.unwrap_or(fill.rule),
+            ..fill
+        });
+        path.stroke = self.stroke;
+        path.paint_order = self.paint_order;
+        if let Some(transform) = self.transform {
+            path.transform = transform;
+        }
+        if self.opacity.is_none()
+            && self.clip.is_none()
+            && self.filters.is_empty()
+            && self.blend_mode == BlendMode::Normal
+            && self.markup.link.is_none()
+        {
+            path.id = self.id.unwrap_or_default();
+            return usvg::Node::new(NodeKind::Path(path));
+        }
+        let group_node = usvg::Node::new(NodeKind::Group(Group {
+            id: self.id.unwrap_or_default(),
+            opacity: self.opacity.map_or(Opacity::ONE, Opacity::new_clamped),
+            clip_path: self.clip.map(|clip| clip.0),
+            filters: self.filters.into_iter().map(|filter| filter.0).collect(),
+            blend_mode: self.blend_mode,
+            ..Default::default()
+        }));
+        group_node.append(usvg::Node::new(NodeKind::Path(path)));
+        group_node
+    }
+}
+
+/// Options of a group pushed with [`LyonWriter::group_with`](crate::LyonWriter::group_with).
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{GroupProps, SvgTransform};
+///
+/// let props = GroupProps::new()
+///     .transform(SvgTransform::from_translate(2.0, 2.0))
+///     .id("panel-a")
+///     .opacity(0.8);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct GroupProps {
+    pub(crate) transform: SvgTransform,
+    pub(crate) id: Option<String>,
+    pub(crate) opacity: Option<f32>,
+    pub(crate) clip: Option<ClipId>,
+    pub(crate) filters: Vec<FilterId>,
+    pub(crate) blend_mode: BlendMode,
+    pub(crate) isolate: bool,
+    pub(crate) markup: NodeMarkup,
+}
+
+impl GroupProps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the [`SvgTransform`] of the group, applied to all its children.
+    pub fn transform(mut self, transform: SvgTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Set the `id` attribute of the written `<g>` element.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Add `class` to the `class` attribute of the written `<g>` element.
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.markup.classes.push(class.into());
+        self
+    }
+
+    /// Set the `<title>` of the group, shown as a tooltip over all its children.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.markup.title = Some(title.into());
+        self
+    }
+
+    /// Set the `<desc>` of the group, a longer description for screen readers.
+    pub fn desc(mut self, desc: impl Into<String>) -> Self {
+        self.markup.desc = Some(desc.into());
+        self
+    }
+
+    /// Add the attribute `data-{name}` with `value` to the written `<g>` element, as in
+    /// [`PathProps::data`].
+    pub fn data(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.markup.data.push((name.into(), value.into()));
+        self
+    }
+
+    /// Make the group a hyperlink to `href`, written as an `<a>` element instead of `<g>`.
+    pub fn link(mut self, href: impl Into<String>) -> Self {
+        self.markup.link = Some((href.into(), None));
+        self
+    }
+
+    /// Set where the hyperlink of the group is opened, as in
+    /// [`PathProps::link_target`].
+    pub fn link_target(mut self, target: impl Into<String>) -> Self {
+        if let Some((_, link_target)) = self.markup.link.as_mut() {
+            *link_target = Some(target.into());
+        }
+        self
+    }
+
+    /// Set the opacity of the group (clamped to `[0, 1]`). The group is rendered as a whole
+    /// before being faded, so that its overlapping children do not show through each other.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, GroupProps, LyonWriter, fill};
+    /// use lyon_path::math::point;
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .group_with(GroupProps::new().opacity(0.5), |g| {
+    ///         g.push_circle(point(1.0, 1.0), 1.0, Some(fill(Color::black(), 1.0)), None, None)?;
+    ///         g.push_circle(point(2.0, 1.0), 1.0, Some(fill(Color::black(), 1.0)), None, None)?;
+    ///         Ok(())
+    ///     })
+    ///     .expect("Group should be writable!");
+    /// let svg = writer.to_svg_string().expect("Paths should be writable!");
+    /// assert!(svg.contains("<g opacity=\"0.5\">"));
+    /// ```
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    /// Clip the group with a clip path from [`LyonWriter::define_clip`](crate::LyonWriter::define_clip).
+    pub fn clip(mut self, clip: ClipId) -> Self {
+        self.clip = Some(clip);
+        self
+    }
+
+    /// Apply a filter from [`LyonWriter::define_filter`](crate::LyonWriter::define_filter) to
+    /// the group as a whole. Filters added in succession are applied in order.
+    pub fn filter(mut self, filter: FilterId) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Set the [`BlendMode`] with which the group, rendered as a whole, is composited over
+    /// what is below it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{BlendMode, Color, GroupProps, LyonWriter, fill};
+    /// use lyon_path::math::point;
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_circle(point(1.0, 1.0), 1.0, Some(fill(Color::new_rgb(255, 0, 0), 1.0)), None, None)
+    ///     .expect("Circle should have an area!");
+    /// writer
+    ///     .group_with(GroupProps::new().blend_mode(BlendMode::Multiply), |g| {
+    ///         g.push_circle(point(2.0, 1.0), 1.0, Some(fill(Color::new_rgb(0, 0, 255), 1.0)), None, None)?;
+    ///         Ok(())
+    ///     })
+    ///     .expect("Group should be writable!");
+    /// let svg = writer.to_svg_string().expect("Paths should be writable!");
+    /// assert!(svg.contains("mix-blend-mode:multiply"));
+    /// ```
+    pub fn blend_mode(mut self, mode: BlendMode) -> Self {
+        self.blend_mode = mode;
+        self
+    }
+
+    /// Render the group in its own layer, so that the blend modes of its children only mix
+    /// them with each other and not with what is below the group.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{BlendMode, GroupProps, LyonWriter};
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .group_with(GroupProps::new().isolate(true), |g| {
+    ///         g.group_with(GroupProps::new().blend_mode(BlendMode::Multiply), |_| Ok(()))?;
+    ///         Ok(())
+    ///     })
+    ///     .expect("Group should be writable!");
+    /// let svg = writer.to_svg_string().expect("Group should be writable!");
+    /// assert!(svg.contains("isolation:isolate"));
+    /// ```
+    pub fn isolate(mut self, isolate: bool) -> Self {
+        self.isolate = isolate;
+        self
+    }
+
+    /// Build the group, consuming the props.
+    pub(crate) fn into_group(self) -> Group {
+        Group {
+            id: self.id.unwrap_or_default(),
+            transform: self.transform,
+            opacity: self.opacity.map_or(Opacity::ONE, Opacity::new_clamped),
+            clip_path: self.clip.map(|clip| clip.0),
+            filters: self.filters.into_iter().map(|filter| filter.0).collect(),
+            blend_mode: self.blend_mode,
+            isolate: self.isolate,
+            ..Default::default()
+        }
+    }
+}
+
+/// Markup of a node that [`usvg`] cannot represent, written as a decoration of its element.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct NodeMarkup {
+    pub(crate) classes: Vec<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) desc: Option<String>,
+    /// `href` and `target` of the `<a>` element that the node is written as.
+    pub(crate) link: Option<(String, Option<String>)>,
+    /// Names (without the `data-` prefix) and values of the custom data attributes.
+    pub(crate) data: Vec<(String, String)>,
+}
+
+impl NodeMarkup {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+            && self.title.is_none()
+            && self.desc.is_none()
+            && self.link.is_none()
+            && self.data.is_empty()
+    }
+}
+
+/// Whether `data-{name}` is a valid custom data attribute: `name` is not empty, does not
+/// start with `xml` and is made of lowercase ASCII letters, digits, `-`, `_` and `.`.
+pub(crate) fn is_data_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with("xml")
+        && name.bytes().all(|byte| {
+            byte.is_ascii_lowercase() || byte.is_ascii_digit() || matches!(byte, b'-' | b'_' | b'.')
+        })
+}
+
+/// Builder of a [`Stroke`] that exposes the options not reachable through
+/// [`stroke`](crate::stroke).
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{Color, LineCap, LineJoin, Stroke, StrokeBuilder};
+///
+/// let stroke: Stroke = StrokeBuilder::new(Color::black(), 2.0)
+///     .opacity(0.8)
+///     .cap(LineCap::Round)
+///     .join(LineJoin::Miter)
+///     .miter_limit(10.0)
+///     .dash(vec![4.0, 2.0], 0.0)
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct StrokeBuilder {
+    paint: Paint,
+    width: f32,
+    opacity: f32,
+    cap: LineCap,
+    join: LineJoin,
+    miter_limit: f32,
+    dasharray: Option<Vec<f32>>,
+    dashoffset: f32,
+}
+
+impl StrokeBuilder {
+    /// Opaque stroke of `color` and `width`, with butt caps and miter joins.
+    pub fn new(color: Color, width: f32) -> Self {
+        Self::from_paint(Paint::Color(color), width)
+    }
+
+    /// Opaque stroke of `paint` (e.g., a gradient) and `width`.
+    pub fn from_paint(paint: Paint, width: f32) -> Self {
+        Self {
+            paint,
+            width,
+            opacity: 1.0,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.0,
+            dasharray: None,
+            dashoffset: 0.0,
+        }
+    }
+
+    /// Set the opacity of the stroke (clamped to `[0, 1]`).
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Set the shape of the end of open subpaths.
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    /// Set the shape of the corners between segments.
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    /// Set the limit of the ratio between the miter length and the width, beyond which
+    /// miter joins are beveled. Values lower than 1 are raised to 1.
+    pub fn miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Dash the stroke, as in [`stroke_dashed`](crate::stroke_dashed).
+    pub fn dash(mut self, dasharray: Vec<f32>, dashoffset: f32) -> Self {
+        self.dasharray = normalize_dasharray(dasharray);
+        self.dashoffset = dashoffset;
+        self
+    }
+
+    /// Build the [`Stroke`].
+    ///
+    /// # Panics
+    ///
+    /// If the width is not positive and finite.
+    pub fn build(self) -> Stroke {
+        Stroke {
+            paint: self.paint,
+            dasharray: self.dasharray,
+            dashoffset: self.dashoffset,
+            miterlimit: StrokeMiterlimit::new(if self.miter_limit.is_nan() {
+                4.0
+            } else {
+                self.miter_limit.max(1.0)
+            }),
+            opacity: Opacity::new_clamped(self.opacity),
+            width: StrokeWidth::new(self.width).expect("Put a real width..."),
+            linecap: self.cap,
+            linejoin: self.join,
+        }
+    }
+}
+
+impl From<StrokeBuilder> for Stroke {
+    fn from(builder: StrokeBuilder) -> Self {
+        builder.build()
+    }
+}
@@ -0,0 +1,62 @@
+//! Rasterization of the written SVG with [`resvg`], behind the `raster` feature.
+use resvg::tiny_skia::{Pixmap, Transform};
+use usvg::Tree;
+
+use crate::LyonTranslationError;
+
+/// Render `tree` scaled by `scale`. Text should be already converted to paths.
+fn render_pixmap(tree: &Tree, scale: f32) -> Result<Pixmap, LyonTranslationError> {
+    let width = (tree.size.width() * scale).ceil();
+    let height = (tree.size.height() * scale).ceil();
+    let mut pixmap =
+        Pixmap::new(width as u32, height as u32).ok_or(LyonTranslationError::WrongBoundingBox {
+            min_x: 0.,
+            max_x: width,
+            min_y: 0.,
+            max_y: height,
+        })?;
+    resvg::Tree::from_usvg(tree).render(Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+/// Render `tree` scaled by `scale` to a PNG. Text should be already converted to paths.
+pub(crate) fn render_png(tree: &Tree, scale: f32) -> Result<Vec<u8>, LyonTranslationError> {
+    render_pixmap(tree, scale)?
+        .encode_png()
+        .map_err(|e| LyonTranslationError::IoWrite(Box::new(e)))
+}
+
+/// Draw `tree` scaled by `scale` onto `canvas`, resizing the canvas to fit it.
+#[cfg(all(feature = "canvas", target_arch = "wasm32"))]
+pub(crate) fn render_canvas(
+    tree: &Tree,
+    scale: f32,
+    canvas: &web_sys::HtmlCanvasElement,
+) -> Result<(), LyonTranslationError> {
+    use wasm_bindgen::{Clamped, JsCast};
+    let js_error = |e| LyonTranslationError::IoWrite(format!("{:?}", e).into());
+    let pixmap = render_pixmap(tree, scale)?;
+    // the pixmap is premultiplied but the canvas expects straight alpha
+    let data: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let image = web_sys::ImageData::new_with_u8_clamped_array_and_sh(
+        Clamped(&data),
+        pixmap.width(),
+        pixmap.height(),
+    )
+    .map_err(js_error)?;
+    canvas.set_width(pixmap.width());
+    canvas.set_height(pixmap.height());
+    let context = canvas
+        .get_context("2d")
+        .map_err(js_error)?
+        .and_then(|context| context.dyn_into::<web_sys::CanvasRenderingContext2d>().ok())
+        .ok_or_else(|| LyonTranslationError::IoWrite("no 2d context for canvas".into()))?;
+    context.put_image_data(&image, 0., 0.).map_err(js_error)
+}
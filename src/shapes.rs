@@ -0,0 +1,768 @@
+//! Geometric primitives, built directly as path data.
+use lyon_path::math::{vector, Point};
+use lyon_path::Path;
+use usvg::tiny_skia_path::{PathBuilder, Rect};
+
+use crate::{
+    check_finite, Fill, FillRule, Hatch, LyonTranslationError, LyonWriter, PathData, Stroke,
+    SvgTransform,
+};
+
+/// Distance of the control points of a cubic quarter of a circle of radius 1 to its ends.
+const KAPPA: f32 = 0.552_284_8;
+/// Distance of the control points of a cubic quarter of the quartic superellipse
+/// `|x|^4 + |y|^4 = 1` to its ends, so that the curve passes through its midpoint.
+const SQUIRCLE_KAPPA: f32 = 0.909_031_3;
+/// Maximum number of lines of a grid in each direction, to stop tiny steps from filling
+/// the memory.
+const MAX_GRID_LINES: f32 = 100_000.0;
+
+/// Shape of the corners of a [rounded rectangle](LyonWriter::push_rounded_rect).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CornerShape {
+    /// Quarters of circles.
+    #[default]
+    Circular,
+    /// Quarters of superellipses, whose curvature grows smoothly from the sides (a squircle
+    /// for equal radii).
+    Squircle,
+}
+
+/// Head of an [arrow](LyonWriter::push_arrow), sized relative to the width of its stroke.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrowHead {
+    /// Filled triangle.
+    #[default]
+    Triangle,
+    /// Open head, drawn with the stroke of the shaft.
+    Chevron,
+    /// Filled circle.
+    Dot,
+    /// Just the shaft.
+    None,
+}
+
+/// Bounds of `(x, y, width, height)`, which must have some area.
+pub(crate) fn rect_bounds(
+    (x, y, width, height): (f32, f32, f32, f32),
+) -> Result<Rect, LyonTranslationError> {
+    Rect::from_xywh(x, y, width, height)
+        .filter(|rect| rect.width() > 0.0 && rect.height() > 0.0)
+        .ok_or(LyonTranslationError::WrongBoundingBox {
+            min_x: x,
+            max_x: x + width,
+            min_y: y,
+            max_y: y + height,
+        })
+}
+
+/// Ellipse at `center` with radii `(rx, ry)`, as four cubic curves starting at its rightmost
+/// point.
+pub(crate) fn ellipse(
+    center: Point,
+    (rx, ry): (f32, f32),
+) -> Result<PathData, LyonTranslationError> {
+    let bounds = rect_bounds((center.x - rx, center.y - ry, 2.0 * rx, 2.0 * ry))?;
+    let (cx, cy) = (center.x, center.y);
+    let (kx, ky) = (KAPPA * rx, KAPPA * ry);
+    let mut builder = PathBuilder::with_capacity(6, 13);
+    builder.move_to(bounds.right(), cy);
+    builder.cubic_to(cx + rx, cy + ky, cx + kx, cy + ry, cx, bounds.bottom());
+    builder.cubic_to(cx - kx, cy + ry, cx - rx, cy + ky, bounds.left(), cy);
+    builder.cubic_to(cx - rx, cy - ky, cx - kx, cy - ry, cx, bounds.top());
+    builder.cubic_to(cx + kx, cy - ry, cx + rx, cy - ky, bounds.right(), cy);
+    builder.close();
+    builder.finish().ok_or(LyonTranslationError::SvgFailure)
+}
+
+/// Rectangle with the `radii` of its top-left, top-right, bottom-right and bottom-left
+/// corners, which are scaled down like in CSS if the radii of a side do not fit in it.
+pub(crate) fn rounded_rect(
+    rect: (f32, f32, f32, f32),
+    radii: [f32; 4],
+    shape: CornerShape,
+) -> Result<PathData, LyonTranslationError> {
+    let bounds = rect_bounds(rect)?;
+    let (left, top, right, bottom) = (bounds.left(), bounds.top(), bounds.right(), bounds.bottom());
+    let radii = radii.map(|r| if r.is_finite() { r.max(0.0) } else { 0.0 });
+    let [tl, tr, br, bl] = radii;
+    let scale = [
+        (tl + tr, bounds.width()),
+        (tr + br, bounds.height()),
+        (br + bl, bounds.width()),
+        (bl + tl, bounds.height()),
+    ]
+    .iter()
+    .filter(|(sum, _)| *sum > 0.0)
+    .fold(1.0f32, |scale, (sum, side)| scale.min(side / sum));
+    let [tl, tr, br, bl] = radii.map(|r| r * scale);
+    let kappa = match shape {
+        CornerShape::Circular => KAPPA,
+        CornerShape::Squircle => SQUIRCLE_KAPPA,
+    };
+    // corner, radius, direction in which it is reached and direction in which it is left
+    let corners = [
+        ((right, top), tr, (1.0, 0.0), (0.0, 1.0)),
+        ((right, bottom), br, (0.0, 1.0), (-1.0, 0.0)),
+        ((left, bottom), bl, (-1.0, 0.0), (0.0, -1.0)),
+        ((left, top), tl, (0.0, -1.0), (1.0, 0.0)),
+    ];
+    let mut builder = PathBuilder::with_capacity(10, 17);
+    let mut current = (left + tl, top);
+    builder.move_to(current.0, current.1);
+    for ((x, y), r, (ix, iy), (ox, oy)) in corners {
+        let start = (x - ix * r, y - iy * r);
+        if start != current {
+            builder.line_to(start.0, start.1);
+        }
+        current = (x + ox * r, y + oy * r);
+        if r > 0.0 {
+            let handle = r * (1.0 - kappa);
+            builder.cubic_to(
+                x - ix * handle,
+                y - iy * handle,
+                x + ox * handle,
+                y + oy * handle,
+                current.0,
+                current.1,
+            );
+        }
+    }
+    builder.close();
+    builder.finish().ok_or(LyonTranslationError::SvgFailure)
+}
+
+/// Point at `(radius, angle)` around `center`, where the angle is measured clockwise from
+/// the top (in the y-down coordinates of SVG), and the direction of the circle there.
+fn polar(center: Point, radius: f32, angle: f64) -> ((f32, f32), (f32, f32)) {
+    // so that axis-aligned points are exact, even for angles given in f32
+    let snap = |v: f64| {
+        if v.abs() < 4.0 * f32::EPSILON as f64 {
+            0.0
+        } else {
+            v as f32
+        }
+    };
+    let (sin, cos) = angle.sin_cos();
+    let (sin, cos) = (snap(sin), snap(cos));
+    (
+        (center.x + radius * sin, center.y - radius * cos),
+        (cos, sin),
+    )
+}
+
+/// Closed polygon through the `vertices` at `(radius, angle)` around `center`, as in
+/// [`polar`].
+fn radial_polygon(
+    center: Point,
+    vertices: impl Iterator<Item = (f32, f64)>,
+) -> Result<PathData, LyonTranslationError> {
+    let mut builder = PathBuilder::new();
+    for (i, (radius, angle)) in vertices.enumerate() {
+        let ((x, y), _) = polar(center, radius, angle);
+        if i == 0 {
+            builder.move_to(x, y);
+        } else {
+            builder.line_to(x, y);
+        }
+    }
+    builder.close();
+    builder.finish().ok_or(LyonTranslationError::SvgFailure)
+}
+
+/// Regular polygon of at least 3 `sides` inscribed in the circle at `center` of `radius`,
+/// with a vertex at the top rotated by `rotation` radians clockwise.
+pub(crate) fn regular_polygon(
+    center: Point,
+    radius: f32,
+    sides: usize,
+    rotation: f32,
+) -> Result<PathData, LyonTranslationError> {
+    rect_bounds((
+        center.x - radius,
+        center.y - radius,
+        2.0 * radius,
+        2.0 * radius,
+    ))?;
+    if sides < 3 {
+        return Err(LyonTranslationError::SvgFailure);
+    }
+    let step = std::f64::consts::TAU / sides as f64;
+    radial_polygon(
+        center,
+        (0..sides).map(|i| (radius, rotation as f64 + i as f64 * step)),
+    )
+}
+
+/// Star of at least 2 `points` at `center`, alternating between tips at `outer_radius`, the
+/// first one at the top, and inner vertices at `inner_radius`.
+pub(crate) fn star(
+    center: Point,
+    outer_radius: f32,
+    inner_radius: f32,
+    points: usize,
+) -> Result<PathData, LyonTranslationError> {
+    for radius in [outer_radius, inner_radius] {
+        rect_bounds((
+            center.x - radius,
+            center.y - radius,
+            2.0 * radius,
+            2.0 * radius,
+        ))?;
+    }
+    if points < 2 {
+        return Err(LyonTranslationError::SvgFailure);
+    }
+    let step = std::f64::consts::PI / points as f64;
+    radial_polygon(
+        center,
+        (0..2 * points).map(|i| {
+            let radius = if i % 2 == 0 {
+                outer_radius
+            } else {
+                inner_radius
+            };
+            (radius, i as f64 * step)
+        }),
+    )
+}
+
+/// Append the arc of the circle at `center` of `radius` from the `start` to the `end` angle,
+/// as in [`polar`], with a cubic curve per quarter of a turn at most. The arc is connected
+/// to the current subpath if `connect`.
+fn append_arc(
+    builder: &mut PathBuilder,
+    center: Point,
+    radius: f32,
+    (start, end): (f64, f64),
+    connect: bool,
+) {
+    let sweep = end - start;
+    let segments = (sweep.abs() / std::f64::consts::FRAC_PI_2 - 1e-6)
+        .ceil()
+        .max(1.0);
+    let step = sweep / segments;
+    // length of the handles of the cubic curves, relative to the radius
+    let handle = radius * (4.0 / 3.0 * (step / 4.0).tan()) as f32;
+    let ((x, y), _) = polar(center, radius, start);
+    if connect {
+        builder.line_to(x, y);
+    } else {
+        builder.move_to(x, y);
+    }
+    for i in 0..segments as usize {
+        let ((x0, y0), (dx0, dy0)) = polar(center, radius, start + i as f64 * step);
+        let ((x1, y1), (dx1, dy1)) = polar(center, radius, start + (i + 1) as f64 * step);
+        builder.cubic_to(
+            x0 + handle * dx0,
+            y0 + handle * dy0,
+            x1 - handle * dx1,
+            y1 - handle * dy1,
+            x1,
+            y1,
+        );
+    }
+}
+
+/// Start and end angles of `angles`, with a sweep of a turn at most.
+fn sweep_angles((start, end): (f32, f32)) -> Result<(f64, f64), LyonTranslationError> {
+    if !(start.is_finite() && end.is_finite()) {
+        return Err(LyonTranslationError::SvgFailure);
+    }
+    let (start, end) = (start as f64, end as f64);
+    let turn = std::f64::consts::TAU;
+    Ok((start, start + (end - start).clamp(-turn, turn)))
+}
+
+/// Whether the sweep from `start` to `end` is a full turn.
+fn is_full_turn((start, end): (f64, f64)) -> bool {
+    (end - start).abs() >= std::f64::consts::TAU
+}
+
+/// Open arc of the circle at `center` of `radius` between `angles`, in radians clockwise from
+/// the top.
+pub(crate) fn arc(
+    center: Point,
+    radius: f32,
+    angles: (f32, f32),
+) -> Result<PathData, LyonTranslationError> {
+    rect_bounds((
+        center.x - radius,
+        center.y - radius,
+        2.0 * radius,
+        2.0 * radius,
+    ))?;
+    let mut builder = PathBuilder::new();
+    append_arc(&mut builder, center, radius, sweep_angles(angles)?, false);
+    builder.finish().ok_or(LyonTranslationError::SvgFailure)
+}
+
+/// Sector of the annulus at `center` between the `(inner, outer)` radii and `angles`, which
+/// is a pie slice if the inner radius is 0. Full turns are whole circles or rings.
+pub(crate) fn annulus_sector(
+    center: Point,
+    (inner, outer): (f32, f32),
+    angles: (f32, f32),
+) -> Result<PathData, LyonTranslationError> {
+    rect_bounds((center.x - outer, center.y - outer, 2.0 * outer, 2.0 * outer))?;
+    if !(0.0..outer).contains(&inner) {
+        return Err(LyonTranslationError::SvgFailure);
+    }
+    let (start, end) = sweep_angles(angles)?;
+    let full = is_full_turn((start, end));
+    let mut builder = PathBuilder::new();
+    append_arc(&mut builder, center, outer, (start, end), false);
+    if inner > 0.0 {
+        if full {
+            // the hole is drawn in the opposite direction, so that it is not filled
+            builder.close();
+        }
+        append_arc(&mut builder, center, inner, (end, start), !full);
+    } else if !full {
+        builder.line_to(center.x, center.y);
+    }
+    builder.close();
+    builder.finish().ok_or(LyonTranslationError::SvgFailure)
+}
+
+impl<T> LyonWriter<T> {
+    /// Add the rectangle `(x, y, width, height)`, which must have some area.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill, stroke};
+    /// use lyon_path::math::point;
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_rect((0.0, 0.0, 4.0, 2.0), Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Rectangle should have an area!");
+    /// writer
+    ///     .push_circle(point(2.0, 1.0), 1.0, None, Some(stroke(Color::white(), 1.0, 0.1)), None)
+    ///     .expect("Circle should have an area!");
+    /// writer
+    ///     .push_ellipse(point(2.0, 1.0), (2.0, 1.0), None, Some(stroke(Color::white(), 1.0, 0.1)), None)
+    ///     .expect("Ellipse should have an area!");
+    /// assert!(writer.push_rect((0.0, 0.0, 0.0, 2.0), None, None, None).is_err());
+    /// let svg = writer.to_svg_string().expect("Shapes should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 4 0 L 4 2 L 0 2 Z\""));
+    /// assert!(svg.contains("d=\"M 3 1 C 3 1.5522846 2.5522847 2 2 2 C"));
+    /// ```
+    pub fn push_rect(
+        &mut self,
+        rect: (f32, f32, f32, f32),
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = PathBuilder::from_rect(rect_bounds(rect)?);
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add the rectangle `(x, y, width, height)` with rounded corners, given by the `radii`
+    /// of its top-left, top-right, bottom-right and bottom-left corners. As in CSS, the radii
+    /// are scaled down when the ones of a side add up to more than its length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, CornerShape, LyonWriter, fill};
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_rounded_rect(
+    ///         (0.0, 0.0, 4.0, 2.0),
+    ///         [1.0, 0.0, 0.0, 0.0],
+    ///         CornerShape::Circular,
+    ///         Some(fill(Color::black(), 1.0)),
+    ///         None,
+    ///         None,
+    ///     )
+    ///     .expect("Rectangle should have an area!");
+    /// // a pill, since the radii do not fit in the height
+    /// writer
+    ///     .push_rounded_rect((0.0, 4.0, 4.0, 2.0), [2.0; 4], CornerShape::Squircle, None, None, None)
+    ///     .expect("Rectangle should have an area!");
+    /// let svg = writer.to_svg_string().expect("Shapes should be writable!");
+    /// assert!(svg.contains(
+    ///     "d=\"M 1 0 L 4 0 L 4 2 L 0 2 L 0 1 C 0 0.44771525 0.44771525 0 1 0 Z\""
+    /// ));
+    /// ```
+    pub fn push_rounded_rect(
+        &mut self,
+        rect: (f32, f32, f32, f32),
+        radii: [f32; 4],
+        shape: CornerShape,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = rounded_rect(rect, radii, shape)?;
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add a circle at `center`, whose `radius` must be positive.
+    ///
+    /// See [`push_rect`](Self::push_rect) for an example.
+    pub fn push_circle(
+        &mut self,
+        center: Point,
+        radius: f32,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        self.push_ellipse(center, (radius, radius), fill, stroke, transform)
+    }
+
+    /// Add an axis-aligned ellipse at `center` with radii `(rx, ry)`, which must be positive.
+    ///
+    /// See [`push_rect`](Self::push_rect) for an example.
+    pub fn push_ellipse(
+        &mut self,
+        center: Point,
+        radii: (f32, f32),
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = ellipse(center, radii)?;
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add a regular polygon of `sides` (at least 3) inscribed in the circle at `center` of
+    /// positive `radius`, for markers. A vertex is at the top, rotated by `rotation` radians
+    /// clockwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill};
+    /// use lyon_path::math::point;
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// // a diamond
+    /// writer
+    ///     .push_regular_polygon(point(0.0, 0.0), 2.0, 4, 0.0, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Polygon should have an area!");
+    /// writer
+    ///     .push_star(point(10.0, 0.0), 2.0, 1.0, 5, Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Star should have an area!");
+    /// assert!(writer.push_regular_polygon(point(0.0, 0.0), 2.0, 2, 0.0, None, None, None).is_err());
+    /// let svg = writer.to_svg_string().expect("Shapes should be writable!");
+    /// assert!(svg.contains("d=\"M 0 -2 L 2 0 L 0 2 L -2 0 Z\""));
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn push_regular_polygon(
+        &mut self,
+        center: Point,
+        radius: f32,
+        sides: usize,
+        rotation: f32,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = regular_polygon(center, radius, sides, rotation)?;
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add a star of `points` (at least 2) at `center`, with its tips at `outer_radius`, one
+    /// of them at the top, and its inner vertices at `inner_radius`. Both radii must be
+    /// positive.
+    ///
+    /// See [`push_regular_polygon`](Self::push_regular_polygon) for an example.
+    #[allow(clippy::too_many_arguments)]
+    pub fn push_star(
+        &mut self,
+        center: Point,
+        outer_radius: f32,
+        inner_radius: f32,
+        points: usize,
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = star(center, outer_radius, inner_radius, points)?;
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add an open circular arc at `center` of positive `radius`, between the `(start, end)`
+    /// `angles` in radians clockwise from the top. Sweeps beyond a full turn are clamped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, fill, stroke};
+    /// use lyon_path::math::point;
+    /// use std::f32::consts::{FRAC_PI_2, PI};
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_arc(point(0.0, 0.0), 1.0, (0.0, FRAC_PI_2), None, Some(stroke(Color::black(), 1.0, 0.1)), None)
+    ///     .expect("Arc should have a radius!");
+    /// // the first quarter of a pie and of a donut chart
+    /// writer
+    ///     .push_pie_slice(point(0.0, 0.0), 1.0, (0.0, FRAC_PI_2), Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Slice should have a radius!");
+    /// writer
+    ///     .push_annulus_sector(point(0.0, 0.0), (0.5, 1.0), (FRAC_PI_2, PI), Some(fill(Color::black(), 1.0)), None, None)
+    ///     .expect("Sector should have a radius!");
+    /// let svg = writer.to_svg_string().expect("Shapes should be writable!");
+    /// assert!(svg.contains("d=\"M 0 -1 C 0.5522848 -1 1 -0.5522848 1 0\""));
+    /// assert!(svg.contains("d=\"M 0 -1 C 0.5522848 -1 1 -0.5522848 1 0 L 0 0 Z\""));
+    /// assert!(svg.contains("d=\"M 1 0 C 1 0.5522848 0.5522848 1 0 1 L 0 0.5 C"));
+    /// ```
+    pub fn push_arc(
+        &mut self,
+        center: Point,
+        radius: f32,
+        angles: (f32, f32),
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = arc(center, radius, angles)?;
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add a pie slice at `center` of positive `radius`, between the `(start, end)` `angles`
+    /// as in [`push_arc`](Self::push_arc). A full turn is a circle.
+    pub fn push_pie_slice(
+        &mut self,
+        center: Point,
+        radius: f32,
+        angles: (f32, f32),
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = annulus_sector(center, (0.0, radius), angles)?;
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add a sector of a donut at `center` between its `(inner, outer)` radii, where
+    /// `0 <= inner < outer`, and the `(start, end)` `angles` as in [`push_arc`](Self::push_arc).
+    /// A full turn is a ring.
+    pub fn push_annulus_sector(
+        &mut self,
+        center: Point,
+        radii: (f32, f32),
+        angles: (f32, f32),
+        fill: Option<Fill>,
+        stroke: Option<Stroke>,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let data = annulus_sector(center, radii, angles)?;
+        self.push_skia_path(data, fill, stroke, transform)
+    }
+
+    /// Add the lines of `hatch` inside `path`, drawn with `stroke`, as a single path. Nothing is
+    /// added if no line crosses the path.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, Hatch, LyonWriter, stroke};
+    /// use lyon_path::Path;
+    /// use lyon_path::math::point;
+    ///
+    /// let mut builder = Path::builder();
+    /// builder.begin(point(0.0, 0.0));
+    /// builder.line_to(point(4.0, 0.0));
+    /// builder.line_to(point(0.0, 4.0));
+    /// builder.end(true);
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_hatched(&builder.build(), &Hatch::new(0.0, 1.0), stroke(Color::black(), 1.0, 0.1), None)
+    ///     .expect("Hatch should be writable!");
+    /// let svg = writer.to_svg_string().expect("Hatch should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 4 0 M 0 1 L 3 1 M 0 2 L 2 2 M 0 3 L 1 3\""));
+    /// ```
+    pub fn push_hatched(
+        &mut self,
+        path: &Path,
+        hatch: &Hatch,
+        stroke: Stroke,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        check_finite(path.as_slice(), self.nodes.len())?;
+        let lines = hatch.lines(path);
+        if lines.iter().next().is_none() {
+            return Ok(self);
+        }
+        self.push(&lines, None, Some(stroke), transform)
+    }
+
+    /// Add the line segment from `from` to `to`, drawn with `stroke`, e.g., for ticks and
+    /// separators.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, stroke};
+    /// use lyon_path::math::point;
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_line(point(0.0, 0.0), point(0.0, 2.0), stroke(Color::black(), 1.0, 0.1), None)
+    ///     .expect("Line should be writable!");
+    /// let svg = writer.to_svg_string().expect("Line should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 0 2\""));
+    /// ```
+    pub fn push_line(
+        &mut self,
+        from: Point,
+        to: Point,
+        stroke: Stroke,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        self.push_polyline([from, to], None, Some(stroke), transform)
+    }
+
+    /// Add an arrow from `from` to `to`, drawn with `stroke`, that is straight or bent by
+    /// `bend` times its length to the left of its direction (negative bends go to the right).
+    /// The `head` is 4 stroke widths long and 3 wide, and is filled with the paint of the stroke.
+    ///
+    /// The shaft and the head are pushed as two paths, in that order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{ArrowHead, Color, LyonWriter, stroke};
+    /// use lyon_path::math::point;
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_arrow(point(0.0, 0.0), point(10.0, 0.0), 0.0, ArrowHead::Triangle, stroke(Color::black(), 1.0, 1.0), None)
+    ///     .expect("Arrow should have a length!");
+    /// writer
+    ///     .push_arrow(point(0.0, 5.0), point(10.0, 5.0), 0.25, ArrowHead::Chevron, stroke(Color::black(), 1.0, 1.0), None)
+    ///     .expect("Arrow should have a length!");
+    /// let svg = writer.to_svg_string().expect("Arrows should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 6 0\""));
+    /// assert!(svg.contains("fill=\"#000000\" stroke=\"none\" d=\"M 10 0 L 6 1.5 L 6 -1.5 Z\""));
+    /// ```
+    pub fn push_arrow(
+        &mut self,
+        from: Point,
+        to: Point,
+        bend: f32,
+        head: ArrowHead,
+        stroke: Stroke,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let length = (to - from).length();
+        if !(length > 0.0 && length.is_finite() && bend.is_finite()) {
+            return Err(LyonTranslationError::SvgFailure);
+        }
+        // to the left of the direction, in y-down coordinates
+        let normal = vector(to.y - from.y, from.x - to.x) / length;
+        let ctrl = from.lerp(to, 0.5) + normal * bend * length;
+        // direction of the shaft at the tip
+        let direction = (to - ctrl).normalize();
+        let side = vector(-direction.y, direction.x);
+        let width = stroke.width.get();
+        let base = match head {
+            ArrowHead::Triangle => to - direction * 4.0 * width,
+            ArrowHead::Dot => to - direction * 1.5 * width,
+            ArrowHead::Chevron | ArrowHead::None => to,
+        };
+        let mut shaft = PathBuilder::new();
+        shaft.move_to(from.x, from.y);
+        if bend == 0.0 {
+            shaft.line_to(base.x, base.y);
+        } else {
+            shaft.quad_to(ctrl.x, ctrl.y, base.x, base.y);
+        }
+        let shaft = shaft.finish().ok_or(LyonTranslationError::SvgFailure)?;
+        let head_fill = Fill {
+            paint: stroke.paint.clone(),
+            opacity: stroke.opacity,
+            rule: FillRule::NonZero,
+        };
+        let back = to - direction * 4.0 * width;
+        let (left, right) = (back + side * 1.5 * width, back - side * 1.5 * width);
+        let head = match head {
+            ArrowHead::Triangle => {
+                let mut builder = PathBuilder::with_capacity(4, 3);
+                builder.move_to(to.x, to.y);
+                builder.line_to(left.x, left.y);
+                builder.line_to(right.x, right.y);
+                builder.close();
+                Some((builder.finish(), Some(head_fill), None))
+            }
+            ArrowHead::Chevron => {
+                let mut builder = PathBuilder::with_capacity(3, 3);
+                builder.move_to(left.x, left.y);
+                builder.line_to(to.x, to.y);
+                builder.line_to(right.x, right.y);
+                Some((builder.finish(), None, Some(stroke.clone())))
+            }
+            ArrowHead::Dot => Some((
+                Some(ellipse(to, (1.5 * width, 1.5 * width))?),
+                Some(head_fill),
+                None,
+            )),
+            ArrowHead::None => None,
+        };
+        self.push_skia_path(shaft, None, Some(stroke), transform)?;
+        if let Some((data, fill, stroke)) = head {
+            let data = data.ok_or(LyonTranslationError::SvgFailure)?;
+            self.push_skia_path(data, fill, stroke, transform)?;
+        }
+        Ok(self)
+    }
+
+    /// Add the lines of a grid over the rectangle `(x, y, width, height)`, every `x_step`
+    /// and `y_step` from its top-left corner, as a single path drawn with `stroke`. The
+    /// sides of the rectangle are included when they fall on a step.
+    ///
+    /// Fails if the rectangle has no area, if the steps are not positive and finite or if
+    /// they would draw more than 100000 lines in either direction.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roarsvg::{Color, LyonWriter, stroke};
+    ///
+    /// let mut writer = LyonWriter::new();
+    /// writer
+    ///     .push_grid((0.0, 0.0, 2.0, 1.0), 1.0, 1.0, stroke(Color::black(), 0.5, 0.05), None)
+    ///     .expect("Grid should have an area!");
+    /// let svg = writer.to_svg_string().expect("Grid should be writable!");
+    /// assert!(svg.contains("d=\"M 0 0 L 0 1 M 1 0 L 1 1 M 2 0 L 2 1 M 0 0 L 2 0 M 0 1 L 2 1\""));
+    /// ```
+    pub fn push_grid(
+        &mut self,
+        rect: (f32, f32, f32, f32),
+        x_step: f32,
+        y_step: f32,
+        stroke: Stroke,
+        transform: Option<SvgTransform>,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let bounds = rect_bounds(rect)?;
+        if !(x_step > 0.0 && y_step > 0.0 && x_step.is_finite() && y_step.is_finite())
+            || bounds.width() / x_step > MAX_GRID_LINES
+            || bounds.height() / y_step > MAX_GRID_LINES
+        {
+            return Err(LyonTranslationError::SvgFailure);
+        }
+        // steps are counted rather than accumulated, to avoid drifting
+        let lines = |length: f32, step: f32| 0..=(length / step + 1e-4).floor() as usize;
+        let mut builder = PathBuilder::new();
+        for i in lines(bounds.width(), x_step) {
+            let x = bounds.left() + i as f32 * x_step;
+            builder.move_to(x, bounds.top());
+            builder.line_to(x, bounds.bottom());
+        }
+        for i in lines(bounds.height(), y_step) {
+            let y = bounds.top() + i as f32 * y_step;
+            builder.move_to(bounds.left(), y);
+            builder.line_to(bounds.right(), y);
+        }
+        let data = builder.finish().ok_or(LyonTranslationError::SvgFailure)?;
+        self.push_skia_path(data, None, Some(stroke), transform)
+    }
+}
@@ -0,0 +1,208 @@
+//! Simplification of the path data before it is written.
+use usvg::tiny_skia_path::{Path as PathData, PathBuilder, PathSegment, Point};
+
+/// Simplify the runs of line segments of `data` with the Ramer–Douglas–Peucker algorithm,
+/// dropping the points closer than `tolerance` to the simplified polyline. Curves are kept.
+pub(crate) fn decimate(data: &PathData, tolerance: f32) -> Option<PathData> {
+    let mut builder = PathBuilder::with_capacity(data.len(), data.points().len());
+    // the current point followed by the ends of the line segments drawn from it
+    let mut run: Vec<Point> = Vec::new();
+    let mut start = Point::zero();
+    for segment in data.segments() {
+        if !matches!(segment, PathSegment::LineTo(_)) {
+            flush_run(&mut run, tolerance, &mut builder);
+        }
+        match segment {
+            PathSegment::MoveTo(p) => {
+                builder.move_to(p.x, p.y);
+                start = p;
+                run = vec![p];
+            }
+            PathSegment::LineTo(p) => run.push(p),
+            PathSegment::QuadTo(p1, p) => {
+                builder.quad_to(p1.x, p1.y, p.x, p.y);
+                run = vec![p];
+            }
+            PathSegment::CubicTo(p1, p2, p) => {
+                builder.cubic_to(p1.x, p1.y, p2.x, p2.y, p.x, p.y);
+                run = vec![p];
+            }
+            PathSegment::Close => {
+                builder.close();
+                run = vec![start];
+            }
+        }
+    }
+    flush_run(&mut run, tolerance, &mut builder);
+    builder.finish()
+}
+
+/// Snap the coordinates of `data` to a grid of `tolerance`, drawing the curves whose control
+/// points are within `tolerance` of their chord as lines and dropping the lines that collapse
+/// into a point.
+pub(crate) fn quantize(data: &PathData, tolerance: f32) -> Option<PathData> {
+    let snap = |p: Point| {
+        Point::from_xy(
+            (p.x / tolerance).round() * tolerance,
+            (p.y / tolerance).round() * tolerance,
+        )
+    };
+    let mut builder = PathBuilder::with_capacity(data.len(), data.points().len());
+    let mut current = Point::zero();
+    let mut start = Point::zero();
+    for segment in data.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                current = snap(p);
+                start = current;
+                builder.move_to(current.x, current.y);
+            }
+            PathSegment::LineTo(p) => {
+                let p = snap(p);
+                if p != current {
+                    builder.line_to(p.x, p.y);
+                    current = p;
+                }
+            }
+            PathSegment::QuadTo(p1, p) => {
+                let (p1, p) = (snap(p1), snap(p));
+                if segment_distance(p1, current, p) <= tolerance {
+                    if p != current {
+                        builder.line_to(p.x, p.y);
+                    }
+                } else {
+                    builder.quad_to(p1.x, p1.y, p.x, p.y);
+                }
+                current = p;
+            }
+            PathSegment::CubicTo(p1, p2, p) => {
+                let (p1, p2, p) = (snap(p1), snap(p2), snap(p));
+                if segment_distance(p1, current, p) <= tolerance
+                    && segment_distance(p2, current, p) <= tolerance
+                {
+                    if p != current {
+                        builder.line_to(p.x, p.y);
+                    }
+                } else {
+                    builder.cubic_to(p1.x, p1.y, p2.x, p2.y, p.x, p.y);
+                }
+                current = p;
+            }
+            PathSegment::Close => {
+                builder.close();
+                current = start;
+            }
+        }
+    }
+    builder.finish()
+}
+
+/// Draw the simplified line segments of `run`, whose first point is already drawn.
+fn flush_run(run: &mut Vec<Point>, tolerance: f32, builder: &mut PathBuilder) {
+    let keep = douglas_peucker(run, tolerance);
+    for (p, _) in run.iter().zip(keep).skip(1).filter(|(_, keep)| *keep) {
+        builder.line_to(p.x, p.y);
+    }
+    run.clear();
+}
+
+/// Points of the polyline `points` kept by the Ramer–Douglas–Peucker algorithm.
+fn douglas_peucker(points: &[Point], tolerance: f32) -> Vec<bool> {
+    let mut keep = vec![false; points.len()];
+    if points.len() < 3 {
+        keep.fill(true);
+        return keep;
+    }
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    // iterative, since polylines may have millions of points
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let (farthest, distance) = (first + 1..last)
+            .map(|i| (i, segment_distance(points[i], points[first], points[last])))
+            .fold(
+                (first, 0.0),
+                |max, current| {
+                    if current.1 > max.1 {
+                        current
+                    } else {
+                        max
+                    }
+                },
+            );
+        if distance > tolerance {
+            keep[farthest] = true;
+            stack.push((first, farthest));
+            stack.push((farthest, last));
+        }
+    }
+    keep
+}
+
+/// Distance from `p` to the segment from `a` to `b`.
+fn segment_distance(p: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0.0 {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (x, y) = (a.x + t * dx, a.y + t * dy);
+    ((p.x - x) * (p.x - x) + (p.y - y) * (p.y - y)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearly_straight_lines_are_decimated() {
+        let mut builder = PathBuilder::new();
+        builder.move_to(0.0, 0.0);
+        for i in 1..=100 {
+            builder.line_to(i as f32, if i % 2 == 0 { 0.01 } else { -0.01 });
+        }
+        builder.line_to(100.0, 50.0);
+        builder.quad_to(50.0, 60.0, 0.0, 50.0);
+        builder.close();
+        let data = builder.finish().unwrap();
+        let decimated = decimate(&data, 0.1).unwrap();
+        let segments: Vec<PathSegment> = decimated.segments().collect();
+        assert_eq!(
+            segments,
+            vec![
+                PathSegment::MoveTo(Point::from_xy(0.0, 0.0)),
+                PathSegment::LineTo(Point::from_xy(100.0, 0.01)),
+                PathSegment::LineTo(Point::from_xy(100.0, 50.0)),
+                PathSegment::QuadTo(Point::from_xy(50.0, 60.0), Point::from_xy(0.0, 50.0)),
+                PathSegment::Close,
+            ]
+        );
+        // nothing is dropped below the tolerance
+        assert_eq!(decimate(&data, 0.001).unwrap().len(), data.len());
+    }
+
+    #[test]
+    fn coordinates_are_snapped_and_flat_curves_collapse() {
+        let mut builder = PathBuilder::new();
+        builder.move_to(0.12, 0.09);
+        builder.line_to(0.1, 0.11);
+        builder.quad_to(5.0, 0.04, 10.0, 0.0);
+        builder.cubic_to(12.0, 3.0, 14.0, 3.0, 16.0, 0.0);
+        let data = builder.finish().unwrap();
+        let segments: Vec<PathSegment> = quantize(&data, 0.25).unwrap().segments().collect();
+        assert_eq!(
+            segments,
+            vec![
+                PathSegment::MoveTo(Point::from_xy(0.0, 0.0)),
+                PathSegment::LineTo(Point::from_xy(10.0, 0.0)),
+                PathSegment::CubicTo(
+                    Point::from_xy(12.0, 3.0),
+                    Point::from_xy(14.0, 3.0),
+                    Point::from_xy(16.0, 0.0)
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,991 @@
+//! Styled text added with [`LyonWriter::push_rich_text`].
+use std::rc::Rc;
+
+use lyon_path::Path;
+use usvg::fontdb::Database;
+use usvg::{
+    AlignmentBaseline, CharacterPosition, DominantBaseline, Font, FontStretch, FontStyle,
+    LengthAdjust, NodeExt, NodeKind, NonZeroPositiveF32, Opacity, Rect, Text, TextAnchor,
+    TextChunk, TextDecoration, TextDecorationStyle, TextFlow, TextPath, TextRendering, TextSpan,
+    TextToPath, WritingMode,
+};
+
+use crate::props::NodeMarkup;
+use crate::xml::{escape, paint_value, path_data_to_d, write_attributes};
+use crate::{
+    fill, lyon_path_to_usvg, min_an_max, node_bbox, Color, Fill, FontProvider, LineCap, LineJoin,
+    LyonTranslationError, LyonWriter, PaintOrder, Stroke, SvgTransform, EMPTY_BOUNDS,
+};
+
+/// Weight, style and stretch of the font of a [`SpanStyle`].
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{FontDesc, FontStyle};
+///
+/// let bold_italic = FontDesc::new().weight(700).style(FontStyle::Italic);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FontDesc {
+    weight: u16,
+    style: FontStyle,
+    stretch: FontStretch,
+}
+
+impl Default for FontDesc {
+    fn default() -> Self {
+        Self {
+            weight: 400,
+            style: FontStyle::Normal,
+            stretch: FontStretch::Normal,
+        }
+    }
+}
+
+impl FontDesc {
+    /// Normal font: weight 400, not italic nor stretched.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the weight, clamped to `[100, 900]` (400 is normal and 700 is bold).
+    pub fn weight(mut self, weight: u16) -> Self {
+        self.weight = weight.clamp(100, 900);
+        self
+    }
+
+    /// Set the [`FontStyle`] (normal, italic or oblique).
+    pub fn style(mut self, style: FontStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Set the [`FontStretch`] (from ultra-condensed to ultra-expanded).
+    pub fn stretch(mut self, stretch: FontStretch) -> Self {
+        self.stretch = stretch;
+        self
+    }
+}
+
+/// Style of a span of [`RichText`].
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{Color, SpanStyle, stroke};
+///
+/// let style = SpanStyle::new(vec!["Arial".to_string()], 12.0)
+///     .stroke(stroke(Color::white(), 1.0, 0.5))
+///     .letter_spacing(0.5)
+///     .word_spacing(2.0)
+///     .underline()
+///     .line_through();
+/// ```
+#[derive(Clone, Debug)]
+pub struct SpanStyle {
+    font_families: Vec<String>,
+    font_size: f32,
+    font: FontDesc,
+    fill: Option<Fill>,
+    stroke: Option<Stroke>,
+    paint_order: PaintOrder,
+    dominant_baseline: DominantBaseline,
+    alignment_baseline: AlignmentBaseline,
+    letter_spacing: f32,
+    word_spacing: f32,
+    underline: bool,
+    overline: bool,
+    line_through: bool,
+}
+
+impl SpanStyle {
+    /// Style with the first available font of `font_families` at `font_size`, filled
+    /// with black as in SVG.
+    pub fn new(font_families: Vec<String>, font_size: f32) -> Self {
+        Self {
+            font_families,
+            font_size,
+            font: FontDesc::default(),
+            fill: Some(fill(Color::black(), 1.0)),
+            stroke: None,
+            paint_order: PaintOrder::FillAndStroke,
+            dominant_baseline: DominantBaseline::Auto,
+            alignment_baseline: AlignmentBaseline::Auto,
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            underline: false,
+            overline: false,
+            line_through: false,
+        }
+    }
+
+    /// Set the [`FontDesc`] of the span, normal by default.
+    pub fn font(mut self, font: FontDesc) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Set the [`Fill`] of the glyphs.
+    pub fn fill(mut self, fill: Fill) -> Self {
+        self.fill = Some(fill);
+        self
+    }
+
+    /// Do not fill the glyphs.
+    pub fn no_fill(mut self) -> Self {
+        self.fill = None;
+        self
+    }
+
+    /// Set the [`Stroke`] of the glyphs. Text is not stroked by default.
+    pub fn stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = Some(stroke);
+        self
+    }
+
+    /// Set the [`PaintOrder`] of the glyphs, e.g., to paint the stroke under the fill of
+    /// outlined labels.
+    pub fn paint_order(mut self, order: PaintOrder) -> Self {
+        self.paint_order = order;
+        self
+    }
+
+    /// Set the `dominant-baseline` of the span, e.g., [`DominantBaseline::Middle`] to
+    /// vertically center it on the position of the text.
+    pub fn dominant_baseline(mut self, baseline: DominantBaseline) -> Self {
+        self.dominant_baseline = baseline;
+        self
+    }
+
+    /// Set the `alignment-baseline` of the span.
+    pub fn alignment_baseline(mut self, baseline: AlignmentBaseline) -> Self {
+        self.alignment_baseline = baseline;
+        self
+    }
+
+    /// Set the extra space added between characters, in user units.
+    pub fn letter_spacing(mut self, spacing: f32) -> Self {
+        self.letter_spacing = spacing;
+        self
+    }
+
+    /// Set the extra space added between words, in user units.
+    pub fn word_spacing(mut self, spacing: f32) -> Self {
+        self.word_spacing = spacing;
+        self
+    }
+
+    /// Underline the span, with the fill and stroke of the glyphs.
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// Draw a line over the span, with the fill and stroke of the glyphs.
+    pub fn overline(mut self) -> Self {
+        self.overline = true;
+        self
+    }
+
+    /// Strike through the span, with the fill and stroke of the glyphs.
+    pub fn line_through(mut self) -> Self {
+        self.line_through = true;
+        self
+    }
+
+    fn to_span(&self, start: usize, end: usize) -> Result<TextSpan, LyonTranslationError> {
+        let decoration_style = |enabled: bool| {
+            enabled.then(|| TextDecorationStyle {
+                fill: self.fill.clone(),
+                stroke: self.stroke.clone(),
+            })
+        };
+        Ok(TextSpan {
+            start,
+            end,
+            fill: self.fill.clone(),
+            stroke: self.stroke.clone(),
+            paint_order: self.paint_order,
+            font: Font {
+                families: self.font_families.clone(),
+                style: self.font.style,
+                stretch: self.font.stretch,
+                weight: self.font.weight,
+            },
+            font_size: NonZeroPositiveF32::new(self.font_size)
+                .ok_or(LyonTranslationError::FontFailure)?,
+            small_caps: false,
+            apply_kerning: false,
+            decoration: TextDecoration {
+                underline: decoration_style(self.underline),
+                overline: decoration_style(self.overline),
+                line_through: decoration_style(self.line_through),
+            },
+            baseline_shift: Vec::new(),
+            letter_spacing: self.letter_spacing,
+            word_spacing: self.word_spacing,
+            text_length: None,
+            length_adjust: LengthAdjust::SpacingAndGlyphs,
+            visibility: usvg::Visibility::Visible,
+            dominant_baseline: self.dominant_baseline,
+            alignment_baseline: self.alignment_baseline,
+        })
+    }
+}
+
+/// Text made of spans with their own [`SpanStyle`], to be pushed with
+/// [`LyonWriter::push_rich_text`].
+///
+/// Line breaks (`\n`) in the spans start a new line below the previous one, aligned
+/// to the same [`TextAnchor`].
+///
+/// # Example
+///
+/// ```
+/// use roarsvg::{RichText, SpanStyle, SvgTransform, TextAnchor};
+///
+/// let arial = SpanStyle::new(vec!["Arial".to_string()], 12.0);
+/// let text = RichText::new()
+///     .span("roar", arial.clone())
+///     .span("svg", arial.underline())
+///     .anchor(TextAnchor::Middle)
+///     .transform(SvgTransform::from_translate(2.0, 14.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RichText {
+    spans: Vec<(String, SpanStyle)>,
+    transform: SvgTransform,
+    anchor: TextAnchor,
+    line_height: Option<f32>,
+    path: Option<(Path, f32)>,
+    dx: Vec<f32>,
+    dy: Vec<f32>,
+    rotate: Vec<f32>,
+    id: Option<String>,
+    markup: NodeMarkup,
+}
+
+impl Default for RichText {
+    fn default() -> Self {
+        Self {
+            spans: Vec::new(),
+            transform: SvgTransform::default(),
+            anchor: TextAnchor::Start,
+            line_height: None,
+            path: None,
+            dx: Vec::new(),
+            dy: Vec::new(),
+            rotate: Vec::new(),
+            id: None,
+            markup: NodeMarkup::default(),
+        }
+    }
+}
+
+impl RichText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `text` styled with `style`.
+    pub fn span(mut self, text: impl Into<String>, style: SpanStyle) -> Self {
+        self.spans.push((text.into(), style));
+        self
+    }
+
+    /// Set the [`SvgTransform`] of the text.
+    pub fn transform(mut self, transform: SvgTransform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Set the [`TextAnchor`]: whether the text starts, is centered or ends at its position.
+    pub fn anchor(mut self, anchor: TextAnchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Set the distance between the baselines of consecutive lines, in user units.
+    ///
+    /// By default, 1.2 times the largest font size of the spans.
+    pub fn line_height(mut self, line_height: f32) -> Self {
+        self.line_height = Some(line_height);
+        self
+    }
+
+    /// Lay the text along `path` (in the user space of the text), starting at
+    /// `start_offset` from the start of the path, as an SVG `<textPath>`.
+    ///
+    /// Text on a path is expected to be a single line; every line would start at the same
+    /// offset of the path.
+    pub fn on_path(mut self, path: &Path, start_offset: f32) -> Self {
+        self.path = Some((path.clone(), start_offset));
+        self
+    }
+
+    /// Shift each character horizontally by the given offsets (relative to the position
+    /// that it would otherwise have), e.g., for manual kerning. Offsets apply to characters
+    /// in order, excluding line breaks, and the characters beyond the list are not shifted.
+    pub fn dx(mut self, dx: Vec<f32>) -> Self {
+        self.dx = dx;
+        self
+    }
+
+    /// Shift each character vertically by the given offsets, as in [`RichText::dx`].
+    pub fn dy(mut self, dy: Vec<f32>) -> Self {
+        self.dy = dy;
+        self
+    }
+
+    /// Rotate each character by the given angles in degrees. Angles apply to characters in
+    /// order, excluding line breaks, and the last angle applies to the rest of characters.
+    pub fn rotate(mut self, rotate: Vec<f32>) -> Self {
+        self.rotate = rotate;
+        self
+    }
+
+    /// Set the `id` attribute of the written element. The text is written inside a `<g>`
+    /// element, which carries the id and the classes.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Add `class` to the `class` attribute of the written element, as in
+    /// [`RichText::id`].
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.markup.classes.push(class.into());
+        self
+    }
+
+    /// Set the `<title>` of the text, shown as a tooltip, as in [`RichText::id`].
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.markup.title = Some(title.into());
+        self
+    }
+
+    /// Set the `<desc>` of the text, a longer description for screen readers, as in
+    /// [`RichText::id`].
+    pub fn desc(mut self, desc: impl Into<String>) -> Self {
+        self.markup.desc = Some(desc.into());
+        self
+    }
+
+    /// Add the attribute `data-{name}` with `value` to the written element, as in
+    /// [`PathProps::data`](crate::PathProps::data) and [`RichText::id`].
+    pub fn data(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.markup.data.push((name.into(), value.into()));
+        self
+    }
+
+    /// Make the text a hyperlink to `href`, as in [`PathProps::link`](crate::PathProps::link).
+    /// The `<g>` element of [`RichText::id`] is written as an `<a>` element.
+    pub fn link(mut self, href: impl Into<String>) -> Self {
+        self.markup.link = Some((href.into(), None));
+        self
+    }
+
+    /// Set where the hyperlink of the text is opened, as in
+    /// [`PathProps::link_target`](crate::PathProps::link_target).
+    pub fn link_target(mut self, target: impl Into<String>) -> Self {
+        if let Some((_, link_target)) = self.markup.link.as_mut() {
+            *link_target = Some(target.into());
+        }
+        self
+    }
+
+    /// Build the [`Text`] node, with a [`TextChunk`] per line holding its spans.
+    pub(crate) fn into_node(self) -> Result<NodeKind, LyonTranslationError> {
+        let line_height = self.line_height.unwrap_or_else(|| {
+            1.2 * self
+                .spans
+                .iter()
+                .map(|(_, style)| style.font_size)
+                .fold(0.0, f32::max)
+        });
+        // one chunk per line, the first one at the origin of the text
+        let mut lines = vec![(String::new(), Vec::new())];
+        for (span_text, style) in self.spans.iter() {
+            for (i, line_text) in span_text.split('\n').enumerate() {
+                if i > 0 {
+                    lines.push((String::new(), Vec::new()));
+                }
+                if line_text.is_empty() {
+                    continue;
+                }
+                let (text, spans) = lines.last_mut().expect("there is always a line");
+                let start = text.len();
+                text.push_str(line_text);
+                spans.push(style.to_span(start, text.len())?);
+            }
+        }
+        let char_count: usize = lines.iter().map(|(text, _)| text.chars().count()).sum();
+        let text_flow = match self.path {
+            Some((path, start_offset)) => TextFlow::Path(Rc::new(TextPath {
+                start_offset,
+                path: Rc::new(
+                    lyon_path_to_usvg(path.as_slice()).ok_or(LyonTranslationError::SvgFailure)?,
+                ),
+            })),
+            None => TextFlow::Linear,
+        };
+        let chunks = lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, (text, spans))| {
+                let (x, y) = if i == 0 {
+                    (None, None)
+                } else {
+                    (Some(0.0), Some(i as f32 * line_height))
+                };
+                TextChunk {
+                    x,
+                    y,
+                    text,
+                    anchor: self.anchor,
+                    text_flow: text_flow.clone(),
+                    spans,
+                }
+            })
+            .collect();
+        Ok(NodeKind::Text(Text {
+            id: self.id.unwrap_or_default(),
+            positions: (0..char_count)
+                .map(|c| CharacterPosition {
+                    x: None,
+                    y: None,
+                    dx: self.dx.get(c).copied(),
+                    dy: self.dy.get(c).copied(),
+                })
+                .collect(),
+            rotate: match self.rotate.last() {
+                // as in SVG, the last angle applies to the rest of characters
+                Some(&last) => (0..char_count)
+                    .map(|c| self.rotate.get(c).copied().unwrap_or(last))
+                    .collect(),
+                None => Vec::new(),
+            },
+            transform: self.transform,
+            rendering_mode: TextRendering::GeometricPrecision,
+            writing_mode: WritingMode::LeftToRight,
+            chunks,
+        }))
+    }
+}
+
+/// Bounding box of `text` transformed by `ts`, measuring its glyphs with `fonts` or, if they are
+/// not given or lack the glyphs, estimating it from the font size.
+pub(crate) fn text_bbox(text: &Text, ts: SvgTransform, fonts: Option<&Database>) -> Option<Rect> {
+    if let Some(outlines) = fonts.and_then(|fonts| text.convert(fonts, ts)) {
+        return node_bbox(&outlines, ts, None);
+    }
+    // rough advance, ascent and descent of a latin font, in ems
+    let (mut x, mut y) = (0f32, 0f32);
+    let mut bounds = EMPTY_BOUNDS;
+    for chunk in text.chunks.iter().filter(|chunk| !chunk.spans.is_empty()) {
+        let size = chunk
+            .spans
+            .iter()
+            .map(|span| span.font_size.get())
+            .fold(0.0, f32::max);
+        let width = chunk.text.chars().count() as f32 * 0.6 * size;
+        x = chunk.x.unwrap_or(x);
+        y = chunk.y.unwrap_or(y);
+        let left = match chunk.anchor {
+            TextAnchor::Start => x,
+            TextAnchor::Middle => x - width / 2.0,
+            TextAnchor::End => x - width,
+        };
+        if let Some(rect) = Rect::from_ltrb(left, y - 0.8 * size, left + width, y + 0.2 * size) {
+            bounds = min_an_max(bounds, rect);
+        }
+        x = left + width;
+    }
+    let (min_x, max_x, min_y, max_y) = bounds;
+    Rect::from_ltrb(min_x, min_y, max_x, max_y)?.transform(ts)
+}
+
+/// Width of `text` laid out with `style`, or `None` if there are no glyphs to measure.
+fn text_width(text: &str, style: &SpanStyle, fontdb: &Database) -> Option<f32> {
+    let NodeKind::Text(text) = RichText::new().span(text, style.clone()).into_node().ok()? else {
+        return None;
+    };
+    let outlines = text.convert(fontdb, SvgTransform::default())?;
+    let bbox = outlines.calculate_bbox()?;
+    Some(bbox.right().max(0.0))
+}
+
+/// Break `text` into lines no wider than `max_width`, at whitespace.
+///
+/// Existing line breaks are kept, and words wider than `max_width` get a line of their own.
+fn wrap(text: &str, max_width: f32, style: &SpanStyle, fontdb: &Database) -> String {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if line.is_empty() {
+                line.push_str(word);
+                continue;
+            }
+            let candidate = format!("{line} {word}");
+            if text_width(&candidate, style, fontdb).unwrap_or(0.0) > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// SVG markup of `text`, as a `<text>` element preceded by the `<defs>` of the paths of
+/// its chunks, whose ids start with `id`.
+pub(crate) fn text_markup(text: &Text, id: &str) -> String {
+    let mut defs = String::new();
+    let mut out = String::from("<text xml:space=\"preserve\"");
+    let mut attributes = Vec::new();
+    if !text.transform.is_identity() {
+        let SvgTransform {
+            sx,
+            ky,
+            kx,
+            sy,
+            tx,
+            ty,
+        } = text.transform;
+        attributes.push((
+            "transform".to_string(),
+            format!("matrix({sx} {ky} {kx} {sy} {tx} {ty})"),
+        ));
+    }
+    let list = |values: Vec<f32>| {
+        values
+            .iter()
+            .map(f32::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    if text.positions.iter().any(|p| p.dx.is_some()) {
+        let dx = text.positions.iter().map(|p| p.dx.unwrap_or(0.0)).collect();
+        attributes.push(("dx".to_string(), list(dx)));
+    }
+    if text.positions.iter().any(|p| p.dy.is_some()) {
+        let dy = text.positions.iter().map(|p| p.dy.unwrap_or(0.0)).collect();
+        attributes.push(("dy".to_string(), list(dy)));
+    }
+    if !text.rotate.is_empty() {
+        attributes.push(("rotate".to_string(), list(text.rotate.clone())));
+    }
+    write_attributes(&mut out, &attributes);
+    out.push('>');
+    for (i, chunk) in text.chunks.iter().enumerate() {
+        let mut attributes = Vec::new();
+        let anchor = match chunk.anchor {
+            TextAnchor::Start => None,
+            TextAnchor::Middle => Some("middle"),
+            TextAnchor::End => Some("end"),
+        };
+        if let Some(anchor) = anchor {
+            attributes.push(("text-anchor".to_string(), anchor.to_string()));
+        }
+        let element = match chunk.text_flow {
+            TextFlow::Linear => {
+                if let Some(x) = chunk.x {
+                    attributes.push(("x".to_string(), x.to_string()));
+                }
+                if let Some(y) = chunk.y {
+                    attributes.push(("y".to_string(), y.to_string()));
+                }
+                "tspan"
+            }
+            TextFlow::Path(ref text_path) => {
+                let path_id = format!("{id}-path{}", i + 1);
+                defs.push_str(&format!(
+                    "<path id=\"{path_id}\" d=\"{}\"/>",
+                    path_data_to_d(&text_path.path)
+                ));
+                attributes.push(("href".to_string(), format!("#{path_id}")));
+                attributes.push((
+                    "startOffset".to_string(),
+                    text_path.start_offset.to_string(),
+                ));
+                "textPath"
+            }
+        };
+        out.push_str(&format!("<{element}"));
+        write_attributes(&mut out, &attributes);
+        out.push('>');
+        for span in &chunk.spans {
+            out.push_str("<tspan");
+            write_attributes(&mut out, &span_attributes(span));
+            out.push('>');
+            out.push_str(&escape(&chunk.text[span.start..span.end]));
+            out.push_str("</tspan>");
+        }
+        out.push_str(&format!("</{element}>"));
+    }
+    out.push_str("</text>");
+    if defs.is_empty() {
+        out
+    } else {
+        format!("<defs>{defs}</defs>{out}")
+    }
+}
+
+/// Presentation attributes of a [`TextSpan`].
+fn span_attributes(span: &TextSpan) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut push = |name: &str, value: String| attributes.push((name.to_string(), value));
+    let families: Vec<String> = span
+        .font
+        .families
+        .iter()
+        .map(|family| {
+            if family.contains(' ') {
+                format!("'{family}'")
+            } else {
+                family.clone()
+            }
+        })
+        .collect();
+    push("font-family", families.join(", "));
+    push("font-size", span.font_size.get().to_string());
+    if span.font.weight != 400 {
+        push("font-weight", span.font.weight.to_string());
+    }
+    match span.font.style {
+        FontStyle::Normal => (),
+        FontStyle::Italic => push("font-style", "italic".to_string()),
+        FontStyle::Oblique => push("font-style", "oblique".to_string()),
+    }
+    let stretch = match span.font.stretch {
+        FontStretch::UltraCondensed => "ultra-condensed",
+        FontStretch::ExtraCondensed => "extra-condensed",
+        FontStretch::Condensed => "condensed",
+        FontStretch::SemiCondensed => "semi-condensed",
+        FontStretch::Normal => "normal",
+        FontStretch::SemiExpanded => "semi-expanded",
+        FontStretch::Expanded => "expanded",
+        FontStretch::ExtraExpanded => "extra-expanded",
+        FontStretch::UltraExpanded => "ultra-expanded",
+    };
+    if stretch != "normal" {
+        push("font-stretch", stretch.to_string());
+    }
+    match span.fill {
+        Some(ref fill) => {
+            push("fill", paint_value(&fill.paint));
+            if fill.opacity != Opacity::ONE {
+                push("fill-opacity", fill.opacity.get().to_string());
+            }
+        }
+        None => push("fill", "none".to_string()),
+    }
+    if let Some(ref stroke) = span.stroke {
+        push("stroke", paint_value(&stroke.paint));
+        push("stroke-width", stroke.width.get().to_string());
+        if stroke.opacity != Opacity::ONE {
+            push("stroke-opacity", stroke.opacity.get().to_string());
+        }
+        match stroke.linecap {
+            LineCap::Butt => (),
+            LineCap::Round => push("stroke-linecap", "round".to_string()),
+            LineCap::Square => push("stroke-linecap", "square".to_string()),
+        }
+        match stroke.linejoin {
+            LineJoin::Miter => (),
+            LineJoin::MiterClip => push("stroke-linejoin", "miter-clip".to_string()),
+            LineJoin::Round => push("stroke-linejoin", "round".to_string()),
+            LineJoin::Bevel => push("stroke-linejoin", "bevel".to_string()),
+        }
+        if stroke.miterlimit.get() != 4.0 {
+            push("stroke-miterlimit", stroke.miterlimit.get().to_string());
+        }
+        if let Some(ref dasharray) = stroke.dasharray {
+            let dasharray: Vec<String> = dasharray.iter().map(f32::to_string).collect();
+            push("stroke-dasharray", dasharray.join(" "));
+            if stroke.dashoffset != 0.0 {
+                push("stroke-dashoffset", stroke.dashoffset.to_string());
+            }
+        }
+    }
+    if span.paint_order == PaintOrder::StrokeAndFill {
+        push("paint-order", "stroke".to_string());
+    }
+    let decorations: Vec<&str> = [
+        (span.decoration.underline.is_some(), "underline"),
+        (span.decoration.overline.is_some(), "overline"),
+        (span.decoration.line_through.is_some(), "line-through"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, name)| enabled.then_some(name))
+    .collect();
+    if !decorations.is_empty() {
+        push("text-decoration", decorations.join(" "));
+    }
+    if span.letter_spacing != 0.0 {
+        push("letter-spacing", span.letter_spacing.to_string());
+    }
+    if span.word_spacing != 0.0 {
+        push("word-spacing", span.word_spacing.to_string());
+    }
+    let dominant_baseline = match span.dominant_baseline {
+        DominantBaseline::Auto => None,
+        DominantBaseline::UseScript => Some("use-script"),
+        DominantBaseline::NoChange => Some("no-change"),
+        DominantBaseline::ResetSize => Some("reset-size"),
+        DominantBaseline::Ideographic => Some("ideographic"),
+        DominantBaseline::Alphabetic => Some("alphabetic"),
+        DominantBaseline::Hanging => Some("hanging"),
+        DominantBaseline::Mathematical => Some("mathematical"),
+        DominantBaseline::Central => Some("central"),
+        DominantBaseline::Middle => Some("middle"),
+        DominantBaseline::TextAfterEdge => Some("text-after-edge"),
+        DominantBaseline::TextBeforeEdge => Some("text-before-edge"),
+    };
+    if let Some(baseline) = dominant_baseline {
+        push("dominant-baseline", baseline.to_string());
+    }
+    let alignment_baseline = match span.alignment_baseline {
+        AlignmentBaseline::Auto => None,
+        AlignmentBaseline::Baseline => Some("baseline"),
+        AlignmentBaseline::BeforeEdge => Some("before-edge"),
+        AlignmentBaseline::TextBeforeEdge => Some("text-before-edge"),
+        AlignmentBaseline::Middle => Some("middle"),
+        AlignmentBaseline::Central => Some("central"),
+        AlignmentBaseline::AfterEdge => Some("after-edge"),
+        AlignmentBaseline::TextAfterEdge => Some("text-after-edge"),
+        AlignmentBaseline::Ideographic => Some("ideographic"),
+        AlignmentBaseline::Alphabetic => Some("alphabetic"),
+        AlignmentBaseline::Hanging => Some("hanging"),
+        AlignmentBaseline::Mathematical => Some("mathematical"),
+    };
+    if let Some(baseline) = alignment_baseline {
+        push("alignment-baseline", baseline.to_string());
+    }
+    attributes
+}
+
+impl<T: FontProvider> LyonWriter<Option<T>> {
+    /// Add [`RichText`] to the writer, as a [`TextChunk`] per line with one [`TextSpan`] per
+    /// span of the line.
+    ///
+    /// Requires having called [`LyonWriter::add_fonts`] beforehand.
+    ///
+    /// # Errors
+    ///
+    /// [`LyonTranslationError::FontFailure`] if the font size of a span is not positive and
+    /// [`LyonTranslationError::SvgFailure`] if the path of [`RichText::on_path`] is empty.
+    pub fn push_rich_text(&mut self, text: RichText) -> Result<&mut Self, LyonTranslationError> {
+        let mut text = text;
+        self.decorate(&mut text.id, &text.markup)?;
+        self.add(usvg::Node::new(text.into_node()?));
+        Ok(self)
+    }
+}
+
+/// Text is measured with the fonts of the writer before writing, so it must own a
+/// [`Database`].
+impl LyonWriter<Option<usvg::fontdb::Database>> {
+    /// Add `text` styled with `style`, broken into lines (at whitespace) so that each line
+    /// is at most `max_width` wide, as laid out with the fonts of the writer.
+    ///
+    /// Runs of whitespace are collapsed and line breaks (`\n`) in `text` are kept.
+    ///
+    /// # Errors
+    ///
+    /// [`LyonTranslationError::NoFonts`] if the writer has no fonts and
+    /// [`LyonTranslationError::FontFailure`] if the font size is not positive.
+    pub fn push_text_wrapped(
+        &mut self,
+        text: &str,
+        max_width: f32,
+        style: SpanStyle,
+        transform: SvgTransform,
+    ) -> Result<&mut Self, LyonTranslationError> {
+        let fontdb = self.fontdb.as_ref().ok_or(LyonTranslationError::NoFonts)?;
+        let wrapped = wrap(text, max_width, &style, fontdb);
+        self.push_rich_text(RichText::new().span(wrapped, style).transform(transform))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_cover_their_byte_ranges() {
+        let style = SpanStyle::new(vec!["Arial".to_string()], 12.0);
+        let node = RichText::new()
+            .anchor(TextAnchor::End)
+            .span("añ", style.clone().letter_spacing(1.5))
+            .span("", style.clone())
+            .span("b", style.underline().font(FontDesc::new().weight(1000)))
+            .into_node()
+            .unwrap();
+        let NodeKind::Text(text) = node else {
+            panic!("A text node should be built!");
+        };
+        let chunk = &text.chunks[0];
+        assert_eq!(chunk.anchor, TextAnchor::End);
+        assert_eq!(chunk.text, "añb");
+        let ranges: Vec<_> = chunk.spans.iter().map(|s| (s.start, s.end)).collect();
+        assert_eq!(ranges, vec![(0, 3), (3, 4)]);
+        assert!(chunk.spans[1].decoration.underline.is_some());
+        assert_eq!(chunk.spans[0].font.weight, 400);
+        assert_eq!(chunk.spans[0].letter_spacing, 1.5);
+        assert_eq!(chunk.spans[1].font.weight, 900);
+    }
+
+    #[test]
+    fn line_breaks_start_new_chunks() {
+        let style = SpanStyle::new(vec!["Arial".to_string()], 10.0);
+        let node = RichText::new()
+            .span("one\ntw", style.clone())
+            .span("o\n\nfour", style)
+            .into_node()
+            .unwrap();
+        let NodeKind::Text(text) = node else {
+            panic!("A text node should be built!");
+        };
+        let lines: Vec<_> = text
+            .chunks
+            .iter()
+            .map(|chunk| (chunk.text.as_str(), chunk.spans.len(), chunk.y))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("one", 1, None),
+                ("two", 2, Some(12.0)),
+                ("", 0, Some(24.0)),
+                ("four", 1, Some(36.0))
+            ]
+        );
+        assert_eq!(text.positions.len(), 10);
+    }
+
+    #[test]
+    fn text_is_estimated_without_fonts() {
+        let node = RichText::new()
+            .span("abc\n\nde", SpanStyle::new(vec!["Arial".to_string()], 10.0))
+            .transform(SvgTransform::from_translate(5.0, 0.0))
+            .into_node()
+            .unwrap();
+        let NodeKind::Text(ref text) = node else {
+            panic!("A text node should be built!");
+        };
+        let bbox = text_bbox(text, text.transform, None).unwrap();
+        assert_eq!(
+            (bbox.left(), bbox.top(), bbox.right(), bbox.bottom()),
+            (5.0, -8.0, 23.0, 26.0)
+        );
+        let mut writer = LyonWriter::new();
+        writer.push_node(node);
+        let tree = writer.into_tree().unwrap();
+        assert_eq!((tree.size.width(), tree.size.height()), (18.0, 34.0));
+    }
+
+    #[test]
+    fn rich_text_sizes_the_document() {
+        let style = SpanStyle::new(vec!["Arial".to_string()], 10.0);
+        let transform = SvgTransform::from_translate(1000.0, 1000.0);
+        let mut writer = LyonWriter::new().add_fonts(Database::new());
+        writer
+            .push_rich_text(
+                RichText::new()
+                    .span("abc", style.clone())
+                    .transform(transform),
+            )
+            .unwrap();
+        let view_box = writer.into_tree().unwrap().view_box.rect;
+        assert_eq!(
+            (
+                view_box.left(),
+                view_box.top(),
+                view_box.right(),
+                view_box.bottom()
+            ),
+            (1000.0, 992.0, 1018.0, 1002.0)
+        );
+        let mut writer = LyonWriter::new().add_fonts(Database::new());
+        writer
+            .push_text_wrapped("abc", 100.0, style, transform)
+            .unwrap();
+        let view_box = writer.into_tree().unwrap().view_box.rect;
+        assert!(view_box.left() <= 1000.0 && view_box.right() >= 1018.0);
+        assert!(view_box.top() <= 992.0 && view_box.bottom() >= 1002.0);
+    }
+
+    #[test]
+    fn glyphs_can_be_shifted_and_rotated() {
+        let style = SpanStyle::new(vec!["Arial".to_string()], 10.0);
+        let node = RichText::new()
+            .span("añ\nb", style)
+            .dx(vec![1.0])
+            .dy(vec![0.0, 2.0])
+            .rotate(vec![10.0, 20.0])
+            .into_node()
+            .unwrap();
+        let NodeKind::Text(text) = node else {
+            panic!("A text node should be built!");
+        };
+        let dx: Vec<_> = text.positions.iter().map(|p| p.dx).collect();
+        let dy: Vec<_> = text.positions.iter().map(|p| p.dy).collect();
+        assert_eq!(dx, vec![Some(1.0), None, None]);
+        assert!(text
+            .positions
+            .iter()
+            .all(|p| p.x.is_none() && p.y.is_none()));
+        assert_eq!(dy, vec![Some(0.0), Some(2.0), None]);
+        assert_eq!(text.rotate, vec![10.0, 20.0, 20.0]);
+    }
+
+    #[test]
+    fn text_can_follow_a_path() {
+        let mut path_builder = Path::builder();
+        path_builder.begin(lyon_path::math::point(0.0, 0.0));
+        path_builder.quadratic_bezier_to(
+            lyon_path::math::point(5.0, 5.0),
+            lyon_path::math::point(10.0, 0.0),
+        );
+        path_builder.end(false);
+        let style = SpanStyle::new(vec!["Arial".to_string()], 10.0);
+        let node = RichText::new()
+            .span("curved", style.clone())
+            .on_path(&path_builder.build(), 2.0)
+            .into_node()
+            .unwrap();
+        let NodeKind::Text(text) = node else {
+            panic!("A text node should be built!");
+        };
+        let TextFlow::Path(ref text_path) = text.chunks[0].text_flow else {
+            panic!("Text should follow the path!");
+        };
+        assert_eq!(text_path.start_offset, 2.0);
+        assert!(RichText::new()
+            .span("nowhere", style)
+            .on_path(&Path::new(), 0.0)
+            .into_node()
+            .is_err());
+    }
+
+    #[test]
+    fn text_is_wrapped_to_the_max_width() {
+        let mut fontdb = Database::new();
+        fontdb.load_system_fonts();
+        let style = SpanStyle::new(vec!["DejaVu Sans".to_string()], 10.0);
+        let text = "roar roar roar roar\nroar";
+        let Some(one_word) = text_width("roar", &style, &fontdb) else {
+            // no system fonts to measure with
+            return;
+        };
+        let two_words = text_width("roar roar", &style, &fontdb).unwrap();
+        let wrapped = wrap(text, (one_word + two_words) / 2.0, &style, &fontdb);
+        assert_eq!(wrapped, "roar\nroar\nroar\nroar\nroar");
+        let wrapped = wrap(text, two_words + 1.0, &style, &fontdb);
+        assert_eq!(wrapped, "roar roar\nroar roar\nroar");
+    }
+}
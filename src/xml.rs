@@ -0,0 +1,567 @@
+//! Markup that [`usvg`] cannot represent, added on top of its SVG output.
+//!
+//! Elements are matched by their `id`, so every decorated node must have a unique id
+//! in the [`Tree`].
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::rc::Rc;
+
+use usvg::tiny_skia_path::{Path as PathData, PathBuilder, PathSegment};
+use usvg::{Color, NodeExt, NodeKind, Paint, Tree, TreeWriting, XmlOptions};
+
+use crate::text::text_markup;
+
+/// Extra markup of an element of the SVG, identified by its `id`.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Decoration {
+    /// Attributes appended to the element, with unescaped values.
+    pub attributes: Vec<(String, String)>,
+    /// Markup inserted before the children of the element, already escaped.
+    pub children: Vec<String>,
+    /// Attributes removed from the element, as written by [`usvg`].
+    pub removed_attributes: Vec<String>,
+    /// New name of the element.
+    pub rename: Option<String>,
+}
+
+/// Set of [`Decoration`]s to be applied to the serialized [`Tree`].
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Decorations {
+    root_attributes: Vec<(String, String)>,
+    /// Markup inserted at the start of the root `<svg>` element, already escaped.
+    root_children: Vec<String>,
+    elements: HashMap<String, Decoration>,
+}
+
+impl Decorations {
+    /// Add an attribute to the root `<svg>` element, if it is not there already.
+    pub fn root_attribute(&mut self, name: &str, value: &str) {
+        if !self.root_attributes.iter().any(|(n, _)| n == name) {
+            self.root_attributes
+                .push((name.to_string(), value.to_string()));
+        }
+    }
+
+    /// Insert `markup`, already escaped, at the start of the root `<svg>` element.
+    pub fn root_child(&mut self, markup: String) {
+        self.root_children.push(markup);
+    }
+
+    /// Whether the markup inserted by the decorations contains `pattern`. Attribute values
+    /// are not checked, since they are escaped.
+    pub fn markup_contains(&self, pattern: &str) -> bool {
+        self.root_children
+            .iter()
+            .chain(self.elements.values().flat_map(|element| &element.children))
+            .any(|markup| markup.contains(pattern))
+    }
+
+    /// Add the decorations of the elements of `other`, whose ids are changed by `rename`,
+    /// which must not be decorated already.
+    pub fn merge(&mut self, other: Decorations, rename: impl Fn(&mut String)) {
+        for (mut id, decoration) in other.elements {
+            rename(&mut id);
+            self.elements.insert(id, decoration);
+        }
+    }
+
+    /// Get the [`Decoration`] of the element with `id`, creating it if needed.
+    pub fn element(&mut self, id: &str) -> &mut Decoration {
+        self.elements.entry(id.to_string()).or_default()
+    }
+
+    /// Rewrite the SVG string produced by [`usvg`], adding the decorations.
+    pub fn apply(&self, svg: &str) -> String {
+        if self.root_attributes.is_empty()
+            && self.root_children.is_empty()
+            && self.elements.is_empty()
+        {
+            return svg.to_string();
+        }
+        let mut out = String::with_capacity(svg.len());
+        let mut rest = svg;
+        let mut is_root = true;
+        // new names of the open elements, to rename their end tags
+        let mut open: Vec<Option<&str>> = Vec::new();
+        while let Some(start) = rest.find('<') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = tag_end(rest);
+            let tag = &rest[..end];
+            rest = &rest[end..];
+            if tag.starts_with("</") {
+                match open.pop().flatten() {
+                    Some(name) => out.push_str(&format!("</{name}>")),
+                    None => out.push_str(tag),
+                }
+                continue;
+            }
+            if tag.starts_with("<?") || tag.starts_with("<!") {
+                out.push_str(tag);
+                continue;
+            }
+            let (body, closing) = match tag.strip_suffix("/>") {
+                Some(body) => (body, "/>"),
+                None => (&tag[..tag.len() - 1], ">"),
+            };
+            let decoration = attribute(body, "id").and_then(|id| self.elements.get(id));
+            let name = body[1..].split_whitespace().next().unwrap_or_default();
+            let rename = decoration.and_then(|decoration| decoration.rename.as_deref());
+            let mut body = body.to_string();
+            if let Some(decoration) = decoration {
+                for attribute in &decoration.removed_attributes {
+                    body = remove_attribute(&body, attribute);
+                }
+            }
+            if let Some(new_name) = rename {
+                body = format!("<{new_name}{}", &body[1 + name.len()..]);
+            }
+            out.push_str(&body);
+            if is_root {
+                write_attributes(&mut out, &self.root_attributes);
+                // the root is always open, since it holds the group of the document
+                out.push_str(closing);
+                for child in &self.root_children {
+                    out.push_str(child);
+                }
+                if closing == ">" {
+                    open.push(None);
+                }
+                is_root = false;
+                continue;
+            }
+            let Some(decoration) = decoration else {
+                out.push_str(closing);
+                if closing == ">" {
+                    open.push(None);
+                }
+                continue;
+            };
+            write_attributes(&mut out, &decoration.attributes);
+            if decoration.children.is_empty() {
+                out.push_str(closing);
+            } else {
+                out.push('>');
+                for child in &decoration.children {
+                    out.push_str(child);
+                }
+                if closing == "/>" {
+                    out.push_str(&format!("</{}>", rename.unwrap_or(name)));
+                }
+            }
+            if closing == ">" {
+                open.push(rename);
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Descriptive metadata of the whole document.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Metadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub creator: Option<String>,
+    pub license: Option<String>,
+}
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+const CC_NS: &str = "http://creativecommons.org/ns#";
+
+impl Metadata {
+    /// Add the metadata as `<title>`, `<desc>` and a Dublin Core `<metadata>` element at the
+    /// start of the document.
+    pub fn decorate(&self, decorations: &mut Decorations) {
+        if let Some(title) = &self.title {
+            decorations.root_child(format!("<title>{}</title>", escape(title)));
+        }
+        if let Some(description) = &self.description {
+            decorations.root_child(format!("<desc>{}</desc>", escape(description)));
+        }
+        let mut work = String::new();
+        if let Some(title) = &self.title {
+            work.push_str(&format!("<dc:title>{}</dc:title>", escape(title)));
+        }
+        if let Some(description) = &self.description {
+            work.push_str(&format!(
+                "<dc:description>{}</dc:description>",
+                escape(description)
+            ));
+        }
+        if let Some(creator) = &self.creator {
+            work.push_str(&format!(
+                "<dc:creator><cc:Agent><dc:title>{}</dc:title></cc:Agent></dc:creator>",
+                escape(creator)
+            ));
+        }
+        match &self.license {
+            Some(license) if license.contains("://") => work.push_str(&format!(
+                "<cc:license rdf:resource=\"{}\"/>",
+                escape(license)
+            )),
+            Some(license) => work.push_str(&format!("<dc:rights>{}</dc:rights>", escape(license))),
+            None => (),
+        }
+        if work.is_empty() {
+            return;
+        }
+        decorations.root_child(format!(
+            "<metadata><rdf:RDF xmlns:rdf=\"{RDF_NS}\" xmlns:dc=\"{DC_NS}\" \
+             xmlns:cc=\"{CC_NS}\"><cc:Work rdf:about=\"\">{work}</cc:Work></rdf:RDF></metadata>"
+        ));
+    }
+}
+
+/// A [`Tree`] ready to be serialized, with the [`Decorations`] of its elements.
+pub(crate) struct Document {
+    pub tree: Tree,
+    pub decorations: Decorations,
+    /// Names and ids of the groups of the layers.
+    pub layers: Vec<(String, String)>,
+}
+
+impl Document {
+    /// Keep the [`Text`](usvg::Text) nodes as `<text>` elements in the output.
+    ///
+    /// [`usvg`] does not write text, so each text node is wrapped in a group whose content is
+    /// written as a [`Decoration`]. The text node is kept in the tree so that its paint
+    /// servers are written to the `<defs>`.
+    pub fn keep_text(&mut self) {
+        let text_nodes: Vec<usvg::Node> = self
+            .tree
+            .root
+            .descendants()
+            .filter(|node| matches!(*node.borrow(), NodeKind::Text(_)))
+            .collect();
+        for (i, text_node) in text_nodes.into_iter().enumerate() {
+            // the group stands for the text, so it takes its id
+            let id = match *text_node.borrow() {
+                NodeKind::Text(ref text) if !text.id.is_empty() => text.id.clone(),
+                _ => format!("roarsvg-text{}", i + 1),
+            };
+            let group_node = usvg::Node::new(NodeKind::Group(usvg::Group {
+                id: id.clone(),
+                ..Default::default()
+            }));
+            text_node.insert_before(group_node.clone());
+            text_node.detach();
+            if let NodeKind::Text(ref text) = *text_node.borrow() {
+                self.decorations
+                    .element(&id)
+                    .children
+                    .push(text_markup(text, &id));
+            }
+            group_node.append(text_node);
+        }
+    }
+
+    /// Documents with only one of the layers each, in the same coordinate system.
+    pub fn layer_documents(&self) -> Vec<(String, Document)> {
+        self.layers
+            .iter()
+            .filter_map(|(name, id)| {
+                let layer_node = self.tree.node_by_id(id)?;
+                // the layer keeps the transform of its parent, which holds the global one
+                let parent = layer_node.parent()?;
+                let root_node = usvg::Node::new(NodeKind::Group(usvg::Group::default()));
+                let group_node = usvg::Node::new(parent.borrow().clone());
+                group_node.append(layer_node.make_deep_copy());
+                root_node.append(group_node);
+                let tree = Tree {
+                    size: self.tree.size,
+                    view_box: self.tree.view_box,
+                    root: root_node,
+                };
+                let document = Document {
+                    tree,
+                    decorations: self.decorations.clone(),
+                    layers: vec![(name.clone(), id.clone())],
+                };
+                Some((name.clone(), document))
+            })
+            .collect()
+    }
+
+    /// Serialize the document to an SVG string.
+    pub fn to_svg_string(&self) -> String {
+        self.decorations
+            .apply(&self.tree.to_string(&XmlOptions::default()))
+    }
+
+    /// Serialize the document to `writer`, streaming only the path data of its nodes.
+    ///
+    /// The path data is swapped for placeholders before serializing the rest of the
+    /// document (the skeleton) as a string, which is decorated in memory too; the `d`
+    /// attributes are then written straight from the path data, one at a time, while the
+    /// skeleton is copied to `writer`. The path data held as text by the decorations
+    /// (shared path definitions and text paths) is part of the skeleton.
+    pub fn write_to<W: Write>(self, mut writer: W) -> std::io::Result<()> {
+        // the data of every node is swapped, so only the decorations may hold path data
+        // starting as the placeholders
+        let start_x = (0..MAX_PLACEHOLDERS)
+            .map(|i| PLACEHOLDER_X - i as f32)
+            .find(|&x| !self.decorations.markup_contains(&placeholder_prefix(x)))
+            .expect("the decorations hold fewer paths than placeholders");
+        let prefix = placeholder_prefix(start_x);
+        let mut data = Vec::new();
+        swap_path_data(&self.tree.root, start_x, &mut data, &mut HashSet::new());
+        let skeleton = self.to_svg_string();
+        let mut rest = skeleton.as_str();
+        while let Some(start) = rest.find(&prefix) {
+            let value_start = start + prefix.len();
+            let len = rest[value_start..]
+                .find('"')
+                .unwrap_or(rest.len() - value_start);
+            let value = &rest[value_start..value_start + len];
+            // ` d="` is kept
+            writer.write_all(&rest.as_bytes()[..start + 4])?;
+            match placeholder_index(value).and_then(|i| data.get(i)) {
+                Some(path_data) => write_d(path_data, &mut writer)?,
+                None => writer.write_all(&rest.as_bytes()[start + 4..value_start + len])?,
+            }
+            rest = &rest[value_start + len..];
+        }
+        writer.write_all(rest.as_bytes())?;
+        writer.flush()
+    }
+}
+
+/// Write the paths under `group` that share their data as `<use>` elements of a single
+/// `<path>` definition, added at the start of `group`.
+///
+/// The `<use>` elements keep the attributes of the paths, which are inherited by the
+/// definition since it has no style of its own.
+pub(crate) fn use_shared_paths(group: &usvg::Node, decorations: &mut Decorations) {
+    let mut indices: HashMap<*const PathData, usize> = HashMap::new();
+    let mut shared: Vec<(Rc<PathData>, Vec<usvg::Node>)> = Vec::new();
+    for node in group.descendants() {
+        if let NodeKind::Path(ref path) = *node.borrow() {
+            let index = *indices.entry(Rc::as_ptr(&path.data)).or_insert_with(|| {
+                shared.push((path.data.clone(), Vec::new()));
+                shared.len() - 1
+            });
+            shared[index].1.push(node.clone());
+        }
+    }
+    let mut definitions = String::new();
+    let mut uses = 0;
+    let shared = shared.iter().filter(|(_, nodes)| nodes.len() > 1);
+    for (i, (data, nodes)) in shared.enumerate() {
+        let definition = format!("roarsvg-shape{}", i + 1);
+        definitions.push_str(&format!(
+            "<path id=\"{definition}\" d=\"{}\"/>",
+            path_data_to_d(data)
+        ));
+        for node in nodes {
+            let NodeKind::Path(ref mut path) = *node.borrow_mut() else {
+                continue;
+            };
+            if path.id.is_empty() {
+                uses += 1;
+                path.id = format!("roarsvg-use{uses}");
+            }
+            let decoration = decorations.element(&path.id);
+            decoration.rename = Some("use".to_string());
+            decoration.removed_attributes.push("d".to_string());
+            decoration
+                .attributes
+                .push(("href".to_string(), format!("#{definition}")));
+        }
+    }
+    if definitions.is_empty() {
+        return;
+    }
+    let NodeKind::Group(ref mut g) = *group.borrow_mut() else {
+        return;
+    };
+    if g.id.is_empty() {
+        g.id = "roarsvg-shared".to_string();
+    }
+    decorations
+        .element(&g.id)
+        .children
+        .push(format!("<defs>{definitions}</defs>"));
+}
+
+/// First point of the placeholders of [`swap_path_data`] at `(x, -PLACEHOLDER_X)`, with `x`
+/// counting down from it until the point is not found in the decorations.
+const PLACEHOLDER_X: f32 = 16777215.0;
+
+/// Candidates for the `x` of the first point of the placeholders, all exact in `f32`.
+const MAX_PLACEHOLDERS: usize = 1 << 20;
+
+/// Start of the `d` attribute of the placeholders of [`swap_path_data`] starting at `x`,
+/// followed by their index.
+fn placeholder_prefix(x: f32) -> String {
+    format!(" d=\"M {x} -{PLACEHOLDER_X} L ")
+}
+
+/// Replace the data of the paths under `root` (including those of clip paths, masks and
+/// patterns) by placeholders starting at `(start_x, -PLACEHOLDER_X)`, pushing the original
+/// data to `data`.
+fn swap_path_data(
+    root: &usvg::Node,
+    start_x: f32,
+    data: &mut Vec<Rc<PathData>>,
+    placeholders: &mut HashSet<*const PathData>,
+) {
+    for node in root.descendants() {
+        if let NodeKind::Path(ref mut path) = *node.borrow_mut() {
+            if !placeholders.contains(&Rc::as_ptr(&path.data)) {
+                let index = data.len();
+                let mut builder = PathBuilder::new();
+                builder.move_to(start_x, -PLACEHOLDER_X);
+                builder.line_to((index >> 16) as f32, (index & 0xffff) as f32);
+                let placeholder = Rc::new(builder.finish().expect("placeholder is a line"));
+                placeholders.insert(Rc::as_ptr(&placeholder));
+                data.push(std::mem::replace(&mut path.data, placeholder));
+            }
+        }
+        node.subroots(|subroot| swap_path_data(&subroot, start_x, data, placeholders));
+    }
+}
+
+/// Index of the path data of a placeholder, from the rest of its `d` attribute.
+fn placeholder_index(value: &str) -> Option<usize> {
+    let (high, low) = value.split_once(' ')?;
+    Some((high.parse::<usize>().ok()? << 16) + low.parse::<usize>().ok()?)
+}
+
+/// Write the value of the `d` attribute of `data` as [`usvg`] does.
+fn write_d<W: Write>(data: &PathData, writer: &mut W) -> std::io::Result<()> {
+    let mut first = true;
+    for segment in data.segments() {
+        if !first {
+            writer.write_all(b" ")?;
+        }
+        first = false;
+        let (command, points): (&[u8], &[_]) = match segment {
+            PathSegment::MoveTo(p) => (b"M", &[p]),
+            PathSegment::LineTo(p) => (b"L", &[p]),
+            PathSegment::QuadTo(p1, p) => (b"Q", &[p1, p]),
+            PathSegment::CubicTo(p1, p2, p) => (b"C", &[p1, p2, p]),
+            PathSegment::Close => (b"Z", &[]),
+        };
+        writer.write_all(command)?;
+        for p in points {
+            write!(writer, " {} {}", Number(p.x), Number(p.y))?;
+        }
+    }
+    Ok(())
+}
+
+/// Coordinate formatted as [`usvg`] does: integers without decimals and the rest rounded to
+/// 8 decimals.
+struct Number(f32);
+
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.fract() == 0.0 {
+            write!(f, "{}", self.0 as i32)
+        } else {
+            write!(f, "{}", (self.0 * 1e8).round() / 1e8)
+        }
+    }
+}
+
+/// Length of the tag at the start of `s`, taking quoted attribute values into account.
+fn tag_end(s: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return i + 1,
+            _ => (),
+        }
+    }
+    s.len()
+}
+
+/// Value of the attribute `name` in the tag `body`, as written.
+fn attribute<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {name}=\"");
+    let start = body.find(&pattern)? + pattern.len();
+    let len = body[start..].find('"')?;
+    Some(&body[start..start + len])
+}
+
+/// Remove the attribute `name` from the tag `body`.
+fn remove_attribute(body: &str, name: &str) -> String {
+    let pattern = format!(" {name}=\"");
+    let Some(start) = body.find(&pattern) else {
+        return body.to_string();
+    };
+    let value_start = start + pattern.len();
+    match body[value_start..].find('"') {
+        Some(len) => format!("{}{}", &body[..start], &body[value_start + len + 1..]),
+        None => body.to_string(),
+    }
+}
+
+pub(crate) fn write_attributes(out: &mut String, attributes: &[(String, String)]) {
+    for (name, value) in attributes {
+        out.push(' ');
+        out.push_str(name);
+        out.push_str("=\"");
+        out.push_str(&escape(value));
+        out.push('"');
+    }
+}
+
+/// Value of the `d` attribute of `data`, formatted as [`usvg`] does.
+pub(crate) fn path_data_to_d(data: &PathData) -> String {
+    let mut d = Vec::new();
+    write_d(data, &mut d).expect("writing to a Vec does not fail");
+    String::from_utf8(d).expect("path data is ASCII")
+}
+
+/// Value of a `fill` or `stroke` attribute with `paint`.
+pub(crate) fn paint_value(paint: &Paint) -> String {
+    match paint {
+        Paint::Color(Color { red, green, blue }) => format!("#{red:02x}{green:02x}{blue:02x}"),
+        Paint::LinearGradient(gradient) => format!("url(#{})", gradient.id),
+        Paint::RadialGradient(gradient) => format!("url(#{})", gradient.id),
+        Paint::Pattern(pattern) => format!("url(#{})", pattern.id),
+    }
+}
+
+/// Percent-encoded `data:` URI of `svg`.
+///
+/// Only the characters with a special meaning in URIs (or in the HTML and CSS that would
+/// embed them) are encoded, which keeps the URI smaller than its base64 version.
+pub(crate) fn data_uri(svg: &str) -> String {
+    let mut uri = String::from("data:image/svg+xml,");
+    for byte in svg.bytes() {
+        match byte {
+            b'%' | b'#' | b'"' | b'\'' | b'<' | b'>' | b'&' | b'?' | b'(' | b')' | b'\\' => {
+                uri.push_str(&format!("%{byte:02X}"))
+            }
+            // whitespace and non-ASCII bytes
+            byte if byte.is_ascii_whitespace() || !byte.is_ascii() => {
+                uri.push_str(&format!("%{byte:02X}"))
+            }
+            byte => uri.push(byte as char),
+        }
+    }
+    uri
+}
+
+/// Escape the special XML characters of a text or attribute value.
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}